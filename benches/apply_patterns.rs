@@ -0,0 +1,76 @@
+//! Compares [`HostVecExt::apply_patterns`] with going through every pair of hosts like it used
+//! to, without applying the entries.
+//!
+//! Run with `cargo bench --bench apply_patterns`.
+
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sshs::ssh_config::{Host, HostVecExt, Parser};
+
+const HOSTS: usize = 3000;
+const PATTERN_HOSTS: usize = 20;
+const ITERATIONS: u32 = 5;
+
+fn hosts() -> Vec<Host> {
+    let mut config = String::new();
+    for i in 0..HOSTS {
+        let _ = writeln!(
+            config,
+            "Host server-{i}\n  Hostname 10.0.{}.{}",
+            i / 256,
+            i % 256
+        );
+    }
+    for i in 0..PATTERN_HOSTS {
+        let _ = writeln!(config, "Host server-{i}* !server-{i}0\n  User user-{i}");
+    }
+    config.push_str("Host *\n  Port 2222\n");
+
+    Parser::new().parse(&mut config.as_bytes()).unwrap()
+}
+
+/// Number of pairs of a pattern host and a host it applies to, found by going through every
+/// host for each pattern host, checking its own regexes and cloning the pattern host on every
+/// match like the previous implementation did.
+fn count_matches_every_pair(hosts: &[Host]) -> usize {
+    let mut matches = 0;
+    for pattern_host in hosts {
+        let regexes = pattern_host.matching_pattern_regexes();
+        if regexes.is_empty() {
+            continue;
+        }
+
+        for host in hosts {
+            if !host.matching_pattern_regexes().is_empty() {
+                continue;
+            }
+
+            if pattern_host.matches(&regexes, &host.get_patterns()[0]) {
+                black_box(pattern_host.clone());
+                matches += 1;
+            }
+        }
+    }
+    matches
+}
+
+fn measure(mut f: impl FnMut() -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(f());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let hosts = hosts();
+
+    let every_pair = measure(|| count_matches_every_pair(black_box(&hosts)));
+    let applied = measure(|| black_box(&hosts).apply_patterns().len());
+
+    println!("{HOSTS} hosts and {} pattern hosts:", PATTERN_HOSTS + 1);
+    println!("  going through every pair:   {every_pair:?}");
+    println!("  HostVecExt::apply_patterns: {applied:?}");
+}
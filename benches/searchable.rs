@@ -0,0 +1,69 @@
+//! Compares [`Searchable::search`] with filtering by cloning the matching items.
+//!
+//! Run with `cargo bench --bench searchable`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// The table's search is part of the binary, not of the library
+#[allow(dead_code)]
+#[path = "../src/searchable.rs"]
+mod searchable;
+
+use searchable::Searchable;
+
+const HOSTS: usize = 5000;
+const ITERATIONS: u32 = 200;
+
+#[derive(Clone)]
+struct Host {
+    name: String,
+    destination: String,
+}
+
+fn hosts() -> Vec<Host> {
+    (0..HOSTS)
+        .map(|i| Host {
+            name: format!("server-{i}"),
+            destination: format!("server-{i}.internal.example.com"),
+        })
+        .collect()
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn predicate(host: &&Host, value: &str) -> bool {
+    host.name.contains(value) || host.destination.contains(value)
+}
+
+fn measure(mut search: impl FnMut(&str) -> usize) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        // Typing a search, then erasing it
+        for value in ["s", "se", "ser", "server", "server-1", "server", ""] {
+            black_box(search(black_box(value)));
+        }
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let mut searchable = Searchable::new(hosts(), "", predicate);
+    let indexed = measure(|value| {
+        searchable.search(value);
+        searchable.len()
+    });
+
+    let vec = hosts();
+    let cloned = measure(|value| {
+        let filtered = vec
+            .iter()
+            .filter(|host| value.is_empty() || predicate(host, value))
+            .cloned()
+            .collect::<Vec<_>>();
+        filtered.len()
+    });
+
+    println!("{HOSTS} hosts, time per typed search:");
+    println!("  cloning matching hosts: {cloned:?}");
+    println!("  Searchable::search:     {indexed:?}");
+}
@@ -0,0 +1,38 @@
+use std::process::{Command, Stdio};
+
+/// Command opening a URL in the default browser.
+fn open_command(url: &str) -> (&'static str, Vec<&str>) {
+    if cfg!(target_os = "macos") {
+        return ("open", vec![url]);
+    }
+
+    if cfg!(windows) {
+        // The empty string is the title of the window `start` would open
+        return ("cmd", vec!["/C", "start", "", url]);
+    }
+
+    ("xdg-open", vec![url])
+}
+
+/// Opens the URL in the default browser.
+///
+/// # Errors
+///
+/// Will return `Err` if the open command cannot be found or if it fails.
+pub fn open(url: &str) -> anyhow::Result<()> {
+    let (command, args) = open_command(url);
+
+    let status = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run {command}: {e}"))?;
+
+    if !status.success() {
+        anyhow::bail!("{command} exited with {status}");
+    }
+
+    Ok(())
+}
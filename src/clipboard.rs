@@ -0,0 +1,168 @@
+use anyhow::anyhow;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Where copied text goes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Clipboard {
+    /// OSC 52 inside an SSH session (`$SSH_CONNECTION` is set), the system clipboard otherwise
+    #[default]
+    Auto,
+    /// The system clipboard, through `pbcopy`, `clip`, `wl-copy`, `xclip` or `xsel`
+    System,
+    /// The clipboard of the terminal, through an OSC 52 escape sequence.
+    /// Works over SSH when the terminal supports it
+    Osc52,
+}
+
+impl Clipboard {
+    /// Copies the text to the clipboard.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the text cannot be copied.
+    pub fn copy(self, text: &str) -> anyhow::Result<()> {
+        match self {
+            Clipboard::Auto if std::env::var_os("SSH_CONNECTION").is_some() => copy_osc52(text),
+            Clipboard::Auto | Clipboard::System => copy(text),
+            Clipboard::Osc52 => copy_osc52(text),
+        }
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(
+                    BASE64_ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3f],
+                ));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Escape sequence setting the terminal clipboard to the text.
+///
+/// Inside tmux, the sequence is passed through to the outer terminal.
+fn osc52_sequence(text: &str, is_in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+
+    if is_in_tmux {
+        return format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+
+    sequence
+}
+
+/// Copies the text to the clipboard of the terminal sshs is displayed in.
+///
+/// # Errors
+///
+/// Will return `Err` if the escape sequence cannot be written.
+pub fn copy_osc52(text: &str) -> anyhow::Result<()> {
+    let sequence = osc52_sequence(text, std::env::var_os("TMUX").is_some());
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(sequence.as_bytes())?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Clipboard commands reading the text to copy from stdin, in order of preference.
+fn clipboard_commands() -> Vec<(&'static str, Vec<&'static str>)> {
+    if cfg!(target_os = "macos") {
+        return vec![("pbcopy", vec![])];
+    }
+
+    if cfg!(windows) {
+        return vec![("clip", vec![])];
+    }
+
+    let mut commands = Vec::new();
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        commands.push(("wl-copy", vec![]));
+    }
+    commands.push(("xclip", vec!["-selection", "clipboard"]));
+    commands.push(("xsel", vec!["--clipboard", "--input"]));
+
+    commands
+}
+
+/// Copies the text to the system clipboard using the first available clipboard command.
+///
+/// # Errors
+///
+/// Will return `Err` if no clipboard command is available or if it fails.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    for (command, args) in clipboard_commands() {
+        let child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        child
+            .stdin
+            .take()
+            .ok_or(anyhow!("Failed to open {command} stdin"))?
+            .write_all(text.as_bytes())?;
+
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("{command} exited with {status}");
+        }
+
+        return Ok(());
+    }
+
+    Err(anyhow!("No clipboard command found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for (text, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("root@web.example.com", "cm9vdEB3ZWIuZXhhbXBsZS5jb20="),
+        ] {
+            assert_eq!(base64(text.as_bytes()), encoded, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("foo", false), "\x1b]52;c;Zm9v\x07");
+        assert_eq!(
+            osc52_sequence("foo", true),
+            "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Minimum widths of the table columns, configurable in the `[min_column_widths]` table of the
+//! [`CONFIG_FILE_NAME`](crate::config_file::CONFIG_FILE_NAME) file:
+//!
+//! ```toml
+//! [min_column_widths]
+//! name = 20
+//! destination = 30
+//! ```
+//!
+//! The keys are the column names of `--fields`, the widths are in terminal columns. A minimum
+//! width takes precedence over the third of the table long columns are capped at.
+
+use clap::ValueEnum;
+
+use crate::config_file::{ConfigFileError, Document, Value};
+use crate::ssh::HostField;
+
+pub const MIN_COLUMN_WIDTHS_TABLE: &str = "min_column_widths";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MinColumnWidths {
+    widths: Vec<(HostField, u16)>,
+}
+
+impl MinColumnWidths {
+    /// Reads the `[min_column_widths]` table, the columns it doesn't set have no minimum width.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a key isn't a column or a width isn't a positive integer.
+    pub fn from_document(document: &Document) -> Result<MinColumnWidths, ConfigFileError> {
+        let mut min_column_widths = MinColumnWidths::default();
+
+        let Some(table) = document.table(&[MIN_COLUMN_WIDTHS_TABLE]) else {
+            return Ok(min_column_widths);
+        };
+
+        let invalid = |message: String| ConfigFileError::Invalid {
+            table: MIN_COLUMN_WIDTHS_TABLE.to_string(),
+            message,
+        };
+
+        for (key, value) in table.get_entries() {
+            let column = HostField::from_str(key, true).map_err(|_| {
+                let columns = HostField::value_variants()
+                    .iter()
+                    .filter_map(|column| Some(column.to_possible_value()?.get_name().to_string()))
+                    .collect::<Vec<_>>();
+                invalid(format!(
+                    "unknown column `{key}`, the columns are {}",
+                    columns.join(", ")
+                ))
+            })?;
+
+            let width = match value {
+                Value::Integer(width) => u16::try_from(*width).ok(),
+                _ => None,
+            };
+            let Some(width) = width else {
+                return Err(invalid(format!("`{key}` must be a positive integer")));
+            };
+
+            min_column_widths
+                .widths
+                .retain(|(other, _)| *other != column);
+            min_column_widths.widths.push((column, width));
+        }
+
+        Ok(min_column_widths)
+    }
+
+    /// Minimum width of the column, `0` if it has none.
+    #[must_use]
+    pub fn get(&self, column: HostField) -> u16 {
+        self.widths
+            .iter()
+            .find(|(other, _)| *other == column)
+            .map_or(0, |(_, width)| *width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_document() {
+        let widths =
+            |config: &str| MinColumnWidths::from_document(&Document::parse(config).unwrap());
+
+        assert_eq!(widths("").unwrap(), MinColumnWidths::default());
+
+        let min_column_widths =
+            widths("[min_column_widths]\nname = 20\nproxy-command = 30\n").unwrap();
+        assert_eq!(min_column_widths.get(HostField::Name), 20);
+        assert_eq!(min_column_widths.get(HostField::ProxyCommand), 30);
+        assert_eq!(min_column_widths.get(HostField::User), 0);
+
+        assert!(widths("[min_column_widths]\nnme = 20\n").is_err());
+        assert!(widths("[min_column_widths]\nname = -1\n").is_err());
+        assert!(widths("[min_column_widths]\nname = \"20\"\n").is_err());
+    }
+}
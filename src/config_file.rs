@@ -0,0 +1,584 @@
+//! sshs' own configuration files, stored in [`config_dir`].
+//!
+//! The files are written in a subset of TOML, anything outside of it is a syntax error:
+//!
+//! - `[table]` and `[[array.of.tables]]` headers with bare (`a-z`, `A-Z`, `0-9`, `-` and `_`) or
+//!   quoted dotted names, each `[table]` being defined once.
+//! - `key = value` pairs with a bare or quoted key, each key being set once per table. Dotted
+//!   keys are only supported in the headers.
+//! - `#` comments, on their own line or after a header or a value.
+//! - Basic strings with the `\"`, `\\`, `\n` and `\t` escapes, literal strings, decimal integers
+//!   with optional `_` separators, `true` and `false`, and arrays of these values on one line.
+//!
+//! Multi-line strings and arrays, floats, dates and times, and inline tables aren't supported.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Main configuration file, in [`config_dir`].
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Returns the directory of sshs' configuration files,
+/// `$XDG_CONFIG_HOME/sshs` or `~/.config/sshs` when `XDG_CONFIG_HOME` isn't set.
+#[must_use]
+pub fn config_dir() -> PathBuf {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => PathBuf::from(directory).join("sshs"),
+        _ => PathBuf::from(shellexpand::tilde("~/.config/sshs").as_ref()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    #[must_use]
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    name: Vec<String>,
+    entries: Vec<(String, Value)>,
+}
+
+impl Table {
+    /// Dotted name of the table, empty for the root table.
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_name(&self) -> &[String] {
+        &self.name
+    }
+
+    /// Entries in the order they are written in the file.
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_entries(&self) -> &[(String, Value)] {
+        &self.entries
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    tables: Vec<Table>,
+}
+
+impl Document {
+    /// Reads and parses the file, returns `Ok(None)` if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Option<Document>, ConfigFileError> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(Document::parse(&content)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the content isn't part of the supported TOML subset.
+    pub fn parse(content: &str) -> Result<Document, ConfigFileError> {
+        let mut tables = Vec::new();
+        let mut table = Table::default();
+
+        for (line_index, raw_line) in content.lines().enumerate() {
+            let syntax_error = |message: &str| ConfigFileError::Syntax {
+                line_number: line_index + 1,
+                line: raw_line.to_string(),
+                message: message.to_string(),
+            };
+
+            let mut cursor = Cursor::new(raw_line);
+            cursor.skip_whitespace();
+            if cursor.is_at_end_of_line() {
+                continue;
+            }
+
+            if cursor.eat('[') {
+                let is_array = cursor.eat('[');
+                let name = cursor.parse_key().map_err(syntax_error)?;
+                if !(cursor.eat(']') && (!is_array || cursor.eat(']'))) {
+                    return Err(syntax_error("expected `]` after the table name"));
+                }
+
+                cursor.skip_whitespace();
+                if !cursor.is_at_end_of_line() {
+                    return Err(syntax_error("unexpected characters after the table header"));
+                }
+
+                if !is_array
+                    && (table.name == name || tables.iter().any(|table: &Table| table.name == name))
+                {
+                    return Err(syntax_error("table defined more than once"));
+                }
+
+                tables.push(std::mem::replace(
+                    &mut table,
+                    Table {
+                        name,
+                        entries: Vec::new(),
+                    },
+                ));
+                continue;
+            }
+
+            let key = cursor.parse_key().map_err(syntax_error)?;
+            let [key] = <[String; 1]>::try_from(key)
+                .map_err(|_| syntax_error("dotted keys are only supported in table headers"))?;
+            if !cursor.eat('=') {
+                return Err(syntax_error("expected `=` after the key"));
+            }
+
+            cursor.skip_whitespace();
+            let value = cursor.parse_value().map_err(syntax_error)?;
+
+            cursor.skip_whitespace();
+            if !cursor.is_at_end_of_line() {
+                return Err(syntax_error("unexpected characters after the value"));
+            }
+
+            if table.get(&key).is_some() {
+                return Err(syntax_error("key defined more than once"));
+            }
+            table.entries.push((key, value));
+        }
+
+        tables.push(table);
+
+        Ok(Document { tables })
+    }
+
+    /// Tables in the order they are written in the file, the root table first.
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.iter()
+    }
+
+    /// Returns the boolean set at `key` in the root table, `None` if it isn't set.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value isn't a boolean.
+    pub fn get_bool(&self, key: &str) -> Result<Option<bool>, ConfigFileError> {
+        let Some(value) = self.table(&[]).and_then(|table| table.get(key)) else {
+            return Ok(None);
+        };
+
+        value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| ConfigFileError::Invalid {
+                table: String::new(),
+                message: format!("`{key}` must be a boolean"),
+            })
+    }
+
+    /// Returns the string set at `key` in the root table, `None` if it isn't set.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value isn't a string.
+    pub fn get_str(&self, key: &str) -> Result<Option<&str>, ConfigFileError> {
+        let Some(value) = self.table(&[]).and_then(|table| table.get(key)) else {
+            return Ok(None);
+        };
+
+        value
+            .as_str()
+            .map(Some)
+            .ok_or_else(|| ConfigFileError::Invalid {
+                table: String::new(),
+                message: format!("`{key}` must be a string"),
+            })
+    }
+
+    /// Returns the first table with the dotted `name` (e.g. `["profiles", "work"]`).
+    #[allow(clippy::must_use_candidate)]
+    pub fn table(&self, name: &[&str]) -> Option<&Table> {
+        self.tables.iter().find(|table| {
+            table
+                .name
+                .iter()
+                .map(String::as_str)
+                .eq(name.iter().copied())
+        })
+    }
+}
+
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(line: &'a str) -> Self {
+        Cursor { rest: line }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t']);
+    }
+
+    fn is_at_end_of_line(&self) -> bool {
+        self.rest.is_empty() || self.rest.starts_with('#')
+    }
+
+    /// Consumes `c` and the whitespaces after it if the next character is `c`.
+    fn eat(&mut self, c: char) -> bool {
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                self.skip_whitespace();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Parses a dotted key made of bare (`a-z`, `A-Z`, `0-9`, `-` and `_`) or quoted parts.
+    fn parse_key(&mut self) -> Result<Vec<String>, &'static str> {
+        let mut parts = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            let part = if self.rest.starts_with(['"', '\'']) {
+                self.parse_string()?
+            } else {
+                let end = self
+                    .rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(self.rest.len());
+                if end == 0 {
+                    return Err("expected a key");
+                }
+
+                let (part, rest) = self.rest.split_at(end);
+                self.rest = rest;
+                part.to_string()
+            };
+            parts.push(part);
+
+            self.skip_whitespace();
+            if !self.eat('.') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, &'static str> {
+        if self.rest.starts_with(['"', '\'']) {
+            return self.parse_string().map(Value::String);
+        }
+
+        if self.eat('[') {
+            let mut values = Vec::new();
+            loop {
+                if self.eat(']') {
+                    return Ok(Value::Array(values));
+                }
+
+                values.push(self.parse_value()?);
+                self.skip_whitespace();
+
+                if !self.eat(',') && !self.rest.starts_with(']') {
+                    return Err("expected `,` or `]` in the array");
+                }
+            }
+        }
+
+        let end = self
+            .rest
+            .find([' ', '\t', ',', ']', '#'])
+            .unwrap_or(self.rest.len());
+        let (literal, rest) = self.rest.split_at(end);
+        self.rest = rest;
+
+        match literal {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => literal
+                .replace('_', "")
+                .parse()
+                .map(Value::Integer)
+                .map_err(|_| "expected a string, an integer, a boolean or an array"),
+        }
+    }
+
+    /// Parses a basic (`"..."`, with escapes) or literal (`'...'`) string.
+    fn parse_string(&mut self) -> Result<String, &'static str> {
+        let mut chars = self.rest.char_indices();
+        let Some((_, quote)) = chars.next() else {
+            return Err("expected a string");
+        };
+
+        let mut value = String::new();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.rest = &self.rest[index + c.len_utf8()..];
+                    return Ok(value);
+                }
+                '\\' if quote == '"' => match chars.next() {
+                    Some((_, '"')) => value.push('"'),
+                    Some((_, '\\')) => value.push('\\'),
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    _ => return Err("unsupported escape sequence"),
+                },
+                c => value.push(c),
+            }
+        }
+
+        Err("unterminated string")
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigFileError {
+    Io(std::io::Error),
+    Syntax {
+        line_number: usize,
+        line: String,
+        message: String,
+    },
+    /// The file is well-formed but a table holds an unexpected key or value.
+    Invalid {
+        table: String,
+        message: String,
+    },
+}
+
+impl From<std::io::Error> for ConfigFileError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigFileError::Io(e)
+    }
+}
+
+impl fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFileError::Io(e) => write!(f, "{e}"),
+            ConfigFileError::Syntax {
+                line_number,
+                line,
+                message,
+            } => write!(f, "line {line_number}: {message}: `{}`", line.trim()),
+            ConfigFileError::Invalid { table, message } if table.is_empty() => {
+                write!(f, "{message}")
+            }
+            ConfigFileError::Invalid { table, message } => write!(f, "[{table}]: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigFileError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let content = r#"
+# Comment
+root = "value" # Trailing comment
+
+["prod-* !prod-test"]
+color = 'red'
+label = "PROD \"1\""
+
+[profiles.work]
+paths = ["~/.ssh/config.work", "~/.ssh/shared"]
+count = 1_000
+enabled = true
+
+[[preset]]
+name = "ssh"
+
+[[preset]]
+name = "mosh"
+"#;
+
+        let document = Document::parse(content).unwrap();
+        let tables = document.tables().collect::<Vec<_>>();
+        assert_eq!(tables.len(), 5);
+
+        assert_eq!(tables[0].get("root").and_then(Value::as_str), Some("value"));
+
+        let label = document.table(&["prod-* !prod-test"]).unwrap();
+        assert_eq!(label.get("color").and_then(Value::as_str), Some("red"));
+        assert_eq!(
+            label.get("label").and_then(Value::as_str),
+            Some("PROD \"1\"")
+        );
+
+        let profile = document.table(&["profiles", "work"]).unwrap();
+        assert_eq!(
+            profile.get("paths").and_then(Value::as_array),
+            Some(
+                &[
+                    Value::String("~/.ssh/config.work".to_string()),
+                    Value::String("~/.ssh/shared".to_string()),
+                ][..]
+            )
+        );
+        assert_eq!(profile.get("count").and_then(Value::as_integer), Some(1000));
+        assert_eq!(profile.get("enabled").and_then(Value::as_bool), Some(true));
+
+        let presets = document
+            .tables()
+            .filter(|table| table.get_name() == ["preset"])
+            .filter_map(|table| table.get("name").and_then(Value::as_str))
+            .collect::<Vec<_>>();
+        assert_eq!(presets, ["ssh", "mosh"]);
+    }
+
+    #[test]
+    fn test_get_bool() {
+        let document =
+            Document::parse("enabled = true\nname = \"value\"\n[table]\nnested = false\n").unwrap();
+
+        assert_eq!(document.get_bool("enabled").unwrap(), Some(true));
+        assert_eq!(document.get_bool("nested").unwrap(), None);
+        assert_eq!(
+            document.get_bool("name").unwrap_err().to_string(),
+            "`name` must be a boolean"
+        );
+        assert_eq!(Document::default().get_bool("enabled").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_str() {
+        let document = Document::parse(
+            "tags_annotation = \"tags:\"\nenabled = true\n[table]\nnested = \"value\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(document.get_str("tags_annotation").unwrap(), Some("tags:"));
+        assert_eq!(document.get_str("nested").unwrap(), None);
+        assert_eq!(
+            document.get_str("enabled").unwrap_err().to_string(),
+            "`enabled` must be a string"
+        );
+        assert_eq!(
+            Document::default().get_str("tags_annotation").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (content, line_number, message) in [
+            ("key value", 1, "expected `=` after the key"),
+            ("= 1", 1, "expected a key"),
+            ("\nkey = \"unterminated", 2, "unterminated string"),
+            ("key = \"\\x\"", 1, "unsupported escape sequence"),
+            ("[table\n", 1, "expected `]` after the table name"),
+            ("[[tables]\n", 1, "expected `]` after the table name"),
+            (
+                "[table] key = 1",
+                1,
+                "unexpected characters after the table header",
+            ),
+            ("key = 1\nkey = 2", 2, "key defined more than once"),
+            ("[a]\n[a]", 2, "table defined more than once"),
+            ("key = [1 2]", 1, "expected `,` or `]` in the array"),
+            (
+                "key = [1,\n2]",
+                1,
+                "expected a string, an integer, a boolean or an array",
+            ),
+            (
+                "a.b = 1",
+                1,
+                "dotted keys are only supported in table headers",
+            ),
+            (
+                "key = \"a\" \"b\"",
+                1,
+                "unexpected characters after the value",
+            ),
+            (
+                "key = \"\"\"multi-line\"\"\"",
+                1,
+                "unexpected characters after the value",
+            ),
+            (
+                "key = 1.5",
+                1,
+                "expected a string, an integer, a boolean or an array",
+            ),
+            (
+                "key = 2024-01-01",
+                1,
+                "expected a string, an integer, a boolean or an array",
+            ),
+            (
+                "key = { a = 1 }",
+                1,
+                "expected a string, an integer, a boolean or an array",
+            ),
+        ] {
+            let Err(ConfigFileError::Syntax {
+                line_number: error_line_number,
+                message: error_message,
+                ..
+            }) = Document::parse(content)
+            else {
+                panic!("expected a syntax error for {content:?}");
+            };
+            assert_eq!(error_line_number, line_number, "{content:?}");
+            assert_eq!(error_message, message, "{content:?}");
+        }
+
+        assert_eq!(
+            Document::parse("[a]\n  key = yes # comment")
+                .unwrap_err()
+                .to_string(),
+            "line 2: expected a string, an integer, a boolean or an array: `key = yes # comment`"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Favorite hosts, persisted by name one per line in the `favorites` file of the
+//! [`config_dir`](crate::config_file::config_dir).
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config_file;
+
+pub const FAVORITES_FILE_NAME: &str = "favorites";
+
+#[derive(Debug, Clone, Default)]
+pub struct Favorites {
+    path: Option<PathBuf>,
+    names: BTreeSet<String>,
+}
+
+impl Favorites {
+    /// Path of the favorites file in the sshs configuration directory.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        config_file::config_dir().join(FAVORITES_FILE_NAME)
+    }
+
+    /// Loads the favorites file, there are no favorites if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read.
+    pub fn load(path: &Path) -> io::Result<Favorites> {
+        let names = match std::fs::read_to_string(path) {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Favorites {
+            path: Some(path.to_path_buf()),
+            names,
+        })
+    }
+
+    /// Adds the host to the favorites, or removes it if it already is one, and saves them.
+    /// Returns `true` if the host is now a favorite.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the favorites file cannot be written.
+    pub fn toggle(&mut self, name: &str) -> io::Result<bool> {
+        let is_favorite = if self.names.remove(name) {
+            false
+        } else {
+            self.names.insert(name.to_string());
+            true
+        };
+
+        let Some(path) = &self.path else {
+            return Ok(is_favorite);
+        };
+
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let mut content = self.names.iter().cloned().collect::<Vec<_>>().join("\n");
+        content.push('\n');
+        std::fs::write(path, content)?;
+
+        Ok(is_favorite)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let directory = std::env::temp_dir().join("sshs-test-favorites");
+        let path = directory.join(FAVORITES_FILE_NAME);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let mut favorites = Favorites::load(&path).unwrap();
+        assert!(favorites.is_empty());
+
+        assert!(favorites.toggle("web").unwrap());
+        assert!(favorites.toggle("db").unwrap());
+        assert!(favorites.toggle("cache").unwrap());
+        assert!(!favorites.toggle("db").unwrap());
+
+        let loaded = Favorites::load(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert!(loaded.contains("web"));
+        assert!(loaded.contains("cache"));
+        assert!(!loaded.contains("db"));
+    }
+}
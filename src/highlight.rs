@@ -0,0 +1,136 @@
+//! Look of the selected row of the table, configurable in the `[highlight]` table of the
+//! [`CONFIG_FILE_NAME`](crate::config_file::CONFIG_FILE_NAME) file:
+//!
+//! ```toml
+//! [highlight]
+//! symbol = "arrow"
+//! style = "bold"
+//! ```
+//!
+//! `symbol` is one of the [`SYMBOL_PRESETS`] or the symbol itself (e.g. `"» "`), at most
+//! [`MAX_SYMBOL_WIDTH`] columns wide. `style` is `reversed`, `bold`, `underlined` or a color,
+//! written like the label colors, used as the background of the row.
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Text;
+use std::str::FromStr;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config_file::{ConfigFileError, Document, Value};
+
+pub const HIGHLIGHT_TABLE: &str = "highlight";
+
+/// Widest symbol accepted, the symbol column takes this width on every row.
+pub const MAX_SYMBOL_WIDTH: usize = 4;
+
+/// Named symbols, `bar` being the default one.
+pub const SYMBOL_PRESETS: [(&str, &str); 4] = [
+    ("bar", " █ "),
+    ("arrow", "> "),
+    ("triangle", "▶ "),
+    ("none", ""),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub symbol: Text<'static>,
+    pub style: Style,
+}
+
+impl Default for Highlight {
+    fn default() -> Self {
+        let bar = SYMBOL_PRESETS[0].1;
+
+        Highlight {
+            // Spans the rows taller than a line, leaving their first and last lines empty
+            symbol: Text::from(vec!["".into(), bar.into(), bar.into(), "".into()]),
+            style: Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+}
+
+impl Highlight {
+    /// Reads the `[highlight]` table, the defaults are used for the keys it doesn't set.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the symbol is too wide or the style is unknown.
+    pub fn from_document(document: &Document) -> Result<Highlight, ConfigFileError> {
+        let mut highlight = Highlight::default();
+
+        let Some(table) = document.table(&[HIGHLIGHT_TABLE]) else {
+            return Ok(highlight);
+        };
+
+        let invalid = |message: String| ConfigFileError::Invalid {
+            table: HIGHLIGHT_TABLE.to_string(),
+            message,
+        };
+
+        for (key, value) in table.get_entries() {
+            let Value::String(value) = value else {
+                return Err(invalid(format!("`{key}` must be a string")));
+            };
+
+            match key.as_str() {
+                "symbol" => match SYMBOL_PRESETS.iter().find(|(name, _)| name == value) {
+                    Some((name, _)) if *name == SYMBOL_PRESETS[0].0 => {}
+                    Some((_, symbol)) => highlight.symbol = Text::from(*symbol),
+                    None if value.chars().any(char::is_control) => {
+                        return Err(invalid(format!("`{value}` can't be a symbol")));
+                    }
+                    None if value.width() > MAX_SYMBOL_WIDTH => {
+                        return Err(invalid(format!(
+                            "`{value}` is wider than {MAX_SYMBOL_WIDTH} columns"
+                        )));
+                    }
+                    None => highlight.symbol = Text::from(value.clone()),
+                },
+                "style" => {
+                    highlight.style = match value.as_str() {
+                        "reversed" => Style::default().add_modifier(Modifier::REVERSED),
+                        "bold" => Style::default().add_modifier(Modifier::BOLD),
+                        "underlined" => Style::default().add_modifier(Modifier::UNDERLINED),
+                        color => {
+                            let color = Color::from_str(color)
+                                .map_err(|_| invalid(format!("invalid style `{color}`")))?;
+                            Style::default().bg(color)
+                        }
+                    };
+                }
+                key => return Err(invalid(format!("unknown key `{key}`"))),
+            }
+        }
+
+        Ok(highlight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_document() {
+        let highlight = |config: &str| Highlight::from_document(&Document::parse(config).unwrap());
+
+        assert_eq!(highlight("").unwrap(), Highlight::default());
+        assert_eq!(
+            highlight("[highlight]\nsymbol = \"bar\"\n").unwrap(),
+            Highlight::default()
+        );
+
+        let arrow = highlight("[highlight]\nsymbol = \"arrow\"\nstyle = \"blue\"\n").unwrap();
+        assert_eq!(arrow.symbol, Text::from("> "));
+        assert_eq!(arrow.style, Style::default().bg(Color::Blue));
+
+        let custom = highlight("[highlight]\nsymbol = \"» \"\nstyle = \"bold\"\n").unwrap();
+        assert_eq!(custom.symbol, Text::from("» "));
+        assert_eq!(custom.style, Style::default().add_modifier(Modifier::BOLD));
+
+        assert!(highlight("[highlight]\nsymbol = \"-----> \"\n").is_err());
+        assert!(highlight("[highlight]\nsymbol = \"\\t\"\n").is_err());
+        assert!(highlight("[highlight]\nstyle = \"blinking\"\n").is_err());
+        assert!(highlight("[highlight]\ncolor = \"red\"\n").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Recent searches, persisted one per line in the `history` file of the
+//! [`config_dir`](crate::config_file::config_dir), the most recent first.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config_file;
+
+pub const HISTORY_FILE_NAME: &str = "history";
+
+/// Number of searches kept in the history.
+pub const MAX_HISTORY_LEN: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+impl History {
+    /// Path of the history file in the sshs configuration directory.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        config_file::config_dir().join(HISTORY_FILE_NAME)
+    }
+
+    /// Loads the history file, the history is empty if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read.
+    pub fn load(path: &Path) -> io::Result<History> {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.is_empty())
+                .take(MAX_HISTORY_LEN)
+                .map(str::to_string)
+                .collect(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(History {
+            path: Some(path.to_path_buf()),
+            entries,
+        })
+    }
+
+    /// Moves the search to the top of the history and saves it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the history file cannot be written.
+    pub fn push(&mut self, search: &str) -> io::Result<()> {
+        // Searches are saved one per line
+        let search = search.replace(['\n', '\r'], " ");
+        if search.trim().is_empty() {
+            return Ok(());
+        }
+
+        self.entries.retain(|entry| *entry != search);
+        self.entries.insert(0, search);
+        self.entries.truncate(MAX_HISTORY_LEN);
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        let mut content = self.entries.join("\n");
+        content.push('\n');
+        std::fs::write(path, content)
+    }
+
+    /// Returns the search `index` positions back, `0` being the most recent one.
+    #[allow(clippy::must_use_candidate)]
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    #[cfg(test)]
+    #[allow(clippy::must_use_candidate)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[cfg(test)]
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let directory = std::env::temp_dir().join("sshs-test-history");
+        let path = directory.join(HISTORY_FILE_NAME);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let mut history = History::load(&path).unwrap();
+        assert!(history.is_empty());
+
+        for search in ["web", "db", "web", "  ", "prod\nweb"] {
+            history.push(search).unwrap();
+        }
+        for i in 0..MAX_HISTORY_LEN {
+            history.push(&format!("host{i}")).unwrap();
+        }
+        history.push("db").unwrap();
+
+        let loaded = History::load(&path);
+        std::fs::remove_dir_all(&directory).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.len(), MAX_HISTORY_LEN);
+        assert_eq!(loaded.get(0), Some("db"));
+        assert_eq!(loaded.get(1), Some("host99"));
+        assert_eq!(loaded.get(MAX_HISTORY_LEN - 1), Some("host1"));
+        assert_eq!(loaded.get(MAX_HISTORY_LEN), None);
+    }
+}
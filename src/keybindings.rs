@@ -0,0 +1,482 @@
+//! Key bindings of the table actions, configurable in the `[keybindings]` table of the
+//! [`CONFIG_FILE_NAME`](crate::config_file::CONFIG_FILE_NAME) file:
+//!
+//! ```toml
+//! [keybindings]
+//! down = ["down", "ctrl+j"]
+//! up = ["up", "ctrl+k"]
+//! quit = "ctrl+q"
+//! ```
+//!
+//! A key is written as modifiers (`ctrl`, `alt`, `shift`) and a key name (a character, `enter`,
+//! `esc`, `tab`, `backspace`, `delete`, `insert`, `space`, arrows, `home`, `end`, `pageup`,
+//! `pagedown` or `f1` to `f12`) joined by `+`. Configured actions replace their default keys,
+//! an empty list unbinds the action.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+use crate::config_file::{ConfigFileError, Document, Value};
+
+pub const KEYBINDINGS_TABLE: &str = "keybindings";
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    First,
+    Last,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    Connect,
+    ToggleProxy,
+    Copy,
+    Retry,
+    OpenInBrowser,
+    /// Replace the search with the previous one of the history
+    HistoryPrevious,
+    /// Replace the search with the next one of the history
+    HistoryNext,
+    /// Select the next host matching the search. It only applies while searching, so `tab`
+    /// switches the preset when the search is empty
+    NextMatch,
+    /// Select the previous host matching the search, only while searching
+    PreviousMatch,
+    /// Switch to the next command template preset
+    NextPreset,
+    /// Pin the selected host at the top of the table, or unpin it
+    ToggleFavorite,
+    /// Add the highlighted host to the hosts to connect to at once, or remove it.
+    /// `space` also toggles it while the search is empty
+    ToggleSelection,
+    /// Switch between the ascending and descending sort orders
+    ReverseSort,
+    /// Show all the aliases of every host instead of the ones fitting the column followed by
+    /// the count of the others, or go back to it. The selected host always shows them all
+    ToggleAliases,
+    /// Delete the word before the cursor in the search
+    DeleteWord,
+    /// Move the cursor to the start of the search
+    SearchStart,
+    /// Move the cursor to the end of the search. It only applies while searching, so `ctrl+e`
+    /// toggles the proxy chain when the search is empty
+    SearchEnd,
+    /// Show the jump hosts and proxy commands the selected host is reached through, or hide them
+    ToggleProxyChain,
+}
+
+impl Action {
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["esc", "ctrl+c"],
+            // `ctrl+n` and `ctrl+p` go through the history instead while it is being cycled
+            Action::Down => &["down", "ctrl+j", "ctrl+n"],
+            Action::Up => &["up", "ctrl+k", "ctrl+p"],
+            Action::First => &["home"],
+            Action::Last => &["end"],
+            Action::PageDown => &["pagedown", "ctrl+f"],
+            Action::PageUp => &["pageup", "ctrl+b"],
+            Action::HalfPageDown => &["ctrl+d"],
+            Action::HalfPageUp => &["ctrl+u"],
+            Action::Connect => &["enter"],
+            Action::ToggleProxy => &["ctrl+t"],
+            Action::Copy => &["ctrl+y"],
+            Action::Retry => &["ctrl+r"],
+            Action::OpenInBrowser => &["ctrl+o"],
+            Action::HistoryPrevious => &["ctrl+p"],
+            Action::HistoryNext => &["ctrl+n"],
+            // `tab` selects the next match while searching, the next preset otherwise
+            Action::NextMatch | Action::NextPreset => &["tab"],
+            Action::PreviousMatch => &["backtab"],
+            Action::ToggleFavorite => &["ctrl+s"],
+            Action::ToggleSelection => &["ctrl+space"],
+            Action::ReverseSort => &["alt+s"],
+            Action::ToggleAliases => &["alt+a"],
+            Action::DeleteWord => &["ctrl+w", "alt+backspace"],
+            Action::SearchStart => &["ctrl+a"],
+            // `ctrl+e` moves to the end of the search while searching, toggles the proxy chain
+            // otherwise
+            Action::SearchEnd | Action::ToggleProxyChain => &["ctrl+e"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Returns `true` if the key event is this key.
+    ///
+    /// Keys without modifiers other than characters also match when modifiers are held,
+    /// e.g. `down` matches `ctrl+down`.
+    #[must_use]
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        let (code, modifiers) = match event.code {
+            // Terminals report shifted characters in uppercase, with or without the shift modifier
+            KeyCode::Char(c) if c.is_ascii_uppercase() => (
+                KeyCode::Char(c.to_ascii_lowercase()),
+                event.modifiers | KeyModifiers::SHIFT,
+            ),
+            code => (code, event.modifiers),
+        };
+
+        code == self.code
+            && (modifiers == self.modifiers
+                || (self.modifiers.is_empty() && !matches!(code, KeyCode::Char(_))))
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    fn from_str(descriptor: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid key `{descriptor}`");
+
+        let (modifiers, name) = match descriptor.rsplit_once('+') {
+            // `+` itself, e.g. `ctrl++`
+            Some(("", "")) => ("", "+"),
+            Some((modifiers, "")) => (modifiers.strip_suffix('+').ok_or_else(invalid)?, "+"),
+            Some((modifiers, name)) => (modifiers, name),
+            None => ("", descriptor),
+        };
+
+        let mut key_modifiers = KeyModifiers::NONE;
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            key_modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        // An uppercase character is the character with shift, e.g. `J` is `shift+j`
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if c.is_ascii_uppercase() {
+                key_modifiers |= KeyModifiers::SHIFT;
+            }
+        }
+
+        let name = name.to_lowercase();
+        let code = match name.as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            name => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    (Some('f'), Some(_)) => match name[1..].parse::<u8>() {
+                        Ok(number @ 1..=12) => KeyCode::F(number),
+                        _ => return Err(invalid()),
+                    },
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+
+        Ok(Key {
+            code,
+            modifiers: key_modifiers,
+        })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(number) => write!(f, "f{number}"),
+            code => write!(f, "{}", format!("{code:?}").to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings {
+            bindings: Action::iter()
+                .map(|action| {
+                    let keys = action
+                        .default_keys()
+                        .iter()
+                        .map(|key| key.parse().unwrap())
+                        .collect();
+                    (action, keys)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    /// Reads the `[keybindings]` table, the default keys are used for the actions it doesn't set.
+    ///
+    /// Returns the key bindings along with the unknown action names of the table.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a key is invalid.
+    pub fn from_document(
+        document: &Document,
+    ) -> Result<(Keybindings, Vec<String>), ConfigFileError> {
+        let mut keybindings = Keybindings::default();
+        let mut unknown_actions = Vec::new();
+
+        let Some(table) = document.table(&[KEYBINDINGS_TABLE]) else {
+            return Ok((keybindings, unknown_actions));
+        };
+
+        let invalid = |message: String| ConfigFileError::Invalid {
+            table: KEYBINDINGS_TABLE.to_string(),
+            message,
+        };
+
+        for (name, value) in table.get_entries() {
+            let Ok(action) = Action::from_str(name) else {
+                unknown_actions.push(name.clone());
+                continue;
+            };
+
+            let descriptors = match value {
+                Value::String(descriptor) => vec![descriptor.as_str()],
+                Value::Array(values) => values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .as_str()
+                            .ok_or_else(|| invalid(format!("`{name}` keys must be strings")))
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(invalid(format!("`{name}` must be a key or a list of keys"))),
+            };
+
+            let keys = descriptors
+                .into_iter()
+                .map(|descriptor| {
+                    Key::from_str(descriptor).map_err(|e| invalid(format!("{name}: {e}")))
+                })
+                .collect::<Result<_, _>>()?;
+
+            for binding in &mut keybindings.bindings {
+                if binding.0 == action {
+                    binding.1 = keys;
+                    break;
+                }
+            }
+        }
+
+        Ok((keybindings, unknown_actions))
+    }
+
+    /// Returns the action bound to the key event.
+    #[cfg(test)]
+    #[allow(clippy::must_use_candidate)]
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.actions(event).next()
+    }
+
+    /// Every action bound to the key event, in the order of [`Action`], for the keys bound to
+    /// actions that only apply in some states.
+    pub fn actions<'a>(&'a self, event: &'a KeyEvent) -> impl Iterator<Item = Action> + 'a {
+        self.bindings
+            .iter()
+            .filter(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| *action)
+    }
+
+    /// Returns the first key bound to the action, used in the key hints.
+    #[allow(clippy::must_use_candidate)]
+    pub fn key(&self, action: Action) -> Option<&Key> {
+        self.bindings
+            .iter()
+            .find(|(bound_action, _)| *bound_action == action)
+            .and_then(|(_, keys)| keys.first())
+    }
+
+    /// Names of every action, to report unknown ones.
+    #[must_use]
+    pub fn action_names() -> Vec<String> {
+        Action::iter().map(|action| action.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_default_keybindings() {
+        let keybindings = Keybindings::default();
+
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Down, KeyModifiers::CONTROL)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Char('j'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keybindings
+                .actions(&event(KeyCode::Tab, KeyModifiers::NONE))
+                .collect::<Vec<_>>(),
+            [Action::NextMatch, Action::NextPreset]
+        );
+        assert_eq!(
+            keybindings
+                .actions(&event(KeyCode::Char('e'), KeyModifiers::CONTROL))
+                .collect::<Vec<_>>(),
+            [Action::SearchEnd, Action::ToggleProxyChain]
+        );
+        assert_eq!(
+            keybindings
+                .actions(&event(KeyCode::Char('p'), KeyModifiers::CONTROL))
+                .collect::<Vec<_>>(),
+            [Action::Up, Action::HistoryPrevious]
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            Some(Action::PreviousMatch)
+        );
+        assert_eq!(
+            keybindings.key(Action::Quit).map(ToString::to_string),
+            Some("Esc".to_string())
+        );
+        assert_eq!(
+            keybindings
+                .key(Action::ToggleProxy)
+                .map(ToString::to_string),
+            Some("ctrl+t".to_string())
+        );
+    }
+
+    #[test]
+    fn test_configured_keybindings() {
+        let document = Document::parse(
+            "[keybindings]\ndown = [\"ctrl+h\", \"alt+shift+J\"]\nquit = \"ctrl+q\"\ncopy = []\nunknown = \"x\"\n",
+        )
+        .unwrap();
+        let (keybindings, unknown_actions) = Keybindings::from_document(&document).unwrap();
+
+        assert_eq!(unknown_actions, ["unknown"]);
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Char('h'), KeyModifiers::CONTROL)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keybindings.action(&event(
+                KeyCode::Char('J'),
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            )),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Down, KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Char('y'), KeyModifiers::CONTROL)),
+            None
+        );
+        assert_eq!(keybindings.key(Action::Copy), None);
+
+        // Unmapped actions keep their default keys
+        assert_eq!(
+            keybindings.action(&event(KeyCode::Up, KeyModifiers::NONE)),
+            Some(Action::Up)
+        );
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        for key in ["hyper+a", "ctrl+", "f13", "pgup", ""] {
+            assert!(Key::from_str(key).is_err(), "{key:?}");
+        }
+
+        assert_eq!(
+            Key::from_str("ctrl++"),
+            Ok(Key {
+                code: KeyCode::Char('+'),
+                modifiers: KeyModifiers::CONTROL,
+            })
+        );
+        assert_eq!(
+            Key::from_str("J"),
+            Ok(Key {
+                code: KeyCode::Char('j'),
+                modifiers: KeyModifiers::SHIFT,
+            })
+        );
+        assert_eq!(
+            Key::from_str("F5"),
+            Ok(Key {
+                code: KeyCode::F(5),
+                modifiers: KeyModifiers::NONE,
+            })
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Host labels read from `labels.toml` in the [`config_dir`](crate::config_file::config_dir).
+//!
+//! Each table header holds host name patterns, written like the patterns of an SSH `Host` line:
+//!
+//! ```toml
+//! ["prod-* !prod-sandbox"]
+//! color = "red"
+//! label = "PROD"
+//! ```
+//!
+//! `color` is a color name (e.g. `red`, `lightblue`), a `#rrggbb` hex code or a 256 colors index.
+//! The first table matching a host is used.
+
+use ratatui::style::Color;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config_file::{self, ConfigFileError, Document, Value};
+use crate::ssh_config;
+
+pub const LABELS_FILE_NAME: &str = "labels.toml";
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    host: ssh_config::Host,
+    regexes: Vec<(Regex, bool)>,
+
+    pub color: Option<Color>,
+    pub text: Option<String>,
+}
+
+impl Label {
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        self.host.matches(&self.regexes, name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    labels: Vec<Label>,
+}
+
+impl Labels {
+    /// Path of the labels file in the sshs configuration directory.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        config_file::config_dir().join(LABELS_FILE_NAME)
+    }
+
+    /// Loads the labels file, no labels are returned if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read, parsed or holds invalid labels.
+    pub fn load(path: &Path) -> Result<Labels, ConfigFileError> {
+        match Document::load(path)? {
+            Some(document) => Labels::from_document(&document),
+            None => Ok(Labels::default()),
+        }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if a table holds an unknown key or an invalid value.
+    pub fn from_document(document: &Document) -> Result<Labels, ConfigFileError> {
+        let invalid = |name: &[String], message: String| ConfigFileError::Invalid {
+            table: name.join("."),
+            message,
+        };
+
+        let mut labels = Vec::new();
+        for table in document.tables() {
+            let name = table.get_name();
+            let [patterns] = name else {
+                if name.is_empty() && table.get_entries().is_empty() {
+                    continue;
+                }
+
+                return Err(invalid(
+                    name,
+                    "expected a table of host patterns".to_string(),
+                ));
+            };
+
+            let mut label = Label {
+                host: ssh_config::Host::new(
+                    patterns.split_whitespace().map(str::to_string).collect(),
+                ),
+                regexes: Vec::new(),
+                color: None,
+                text: None,
+            };
+            label.regexes = label.host.matching_pattern_regexes();
+
+            for (key, value) in table.get_entries() {
+                match (key.as_str(), value) {
+                    ("color", Value::String(color)) => {
+                        label.color = Some(
+                            Color::from_str(color)
+                                .map_err(|_| invalid(name, format!("invalid color `{color}`")))?,
+                        );
+                    }
+                    ("label", Value::String(text)) => label.text = Some(text.clone()),
+                    _ => return Err(invalid(name, format!("unexpected key `{key}`"))),
+                }
+            }
+
+            labels.push(label);
+        }
+
+        Ok(Labels { labels })
+    }
+
+    /// Returns the first label matching the host name.
+    #[allow(clippy::must_use_candidate)]
+    pub fn find(&self, name: &str) -> Option<&Label> {
+        self.labels.iter().find(|label| label.matches(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let document = Document::parse(
+            r##"
+["prod-* !prod-sandbox"]
+color = "red"
+label = "PROD"
+
+["prod-sandbox staging"]
+color = "#00ff00"
+"##,
+        )
+        .unwrap();
+        let labels = Labels::from_document(&document).unwrap();
+
+        let label = labels.find("prod-db").unwrap();
+        assert_eq!(label.color, Some(Color::Red));
+        assert_eq!(label.text.as_deref(), Some("PROD"));
+
+        let label = labels.find("prod-sandbox").unwrap();
+        assert_eq!(label.color, Some(Color::Rgb(0, 255, 0)));
+        assert_eq!(label.text, None);
+
+        assert!(labels.find("staging").is_some());
+        assert!(labels.find("dev").is_none());
+    }
+
+    #[test]
+    fn test_invalid_labels() {
+        for content in [
+            "[\"prod-*\"]\ncolor = \"not a color\"",
+            "[\"prod-*\"]\nunknown = \"value\"",
+            "[a.b]\ncolor = \"red\"",
+        ] {
+            let document = Document::parse(content).unwrap();
+            assert!(Labels::from_document(&document).is_err(), "{content:?}");
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! SSH configuration resolution used by the `sshs` terminal user interface.
+//!
+//! [`resolve_hosts`] parses SSH configuration files into the list of hosts displayed by sshs.
+//! The lower-level parser lives in [`ssh_config`].
+
+pub mod ssh;
+pub mod ssh_config;
+pub mod timing;
+
+use std::path::PathBuf;
+
+pub use ssh::{Host, ParseConfigError};
+
+/// Parses the SSH configuration files in order and returns the resolved hosts.
+///
+/// # Errors
+///
+/// Will return `Err` if one of the SSH configuration files cannot be read or parsed.
+pub fn resolve_hosts(paths: &[PathBuf]) -> Result<Vec<Host>, ParseConfigError> {
+    let parser = ssh_config::Parser::new();
+    let mut hosts = Vec::new();
+
+    for path in paths {
+        hosts.extend(ssh::parse_config_file(path, &parser)?);
+    }
+
+    Ok(hosts)
+}
@@ -0,0 +1,118 @@
+//! Non-interactive output of the hosts, used by `--list`.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::ssh;
+
+/// Format of the hosts printed by `--list`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// One host per line, fields separated by tabs
+    #[default]
+    Text,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// Fields printed in the text format when none are chosen, in order.
+pub const DEFAULT_TEXT_FIELDS: [ssh::HostField; 5] = [
+    ssh::HostField::Name,
+    ssh::HostField::Aliases,
+    ssh::HostField::User,
+    ssh::HostField::Destination,
+    ssh::HostField::Port,
+];
+
+/// Writes the host as a single line, with the `fields` in the text format
+/// ([`DEFAULT_TEXT_FIELDS`] when empty) and `default_port` as the port of a host without one.
+///
+/// # Errors
+///
+/// Will return `Err` if the line cannot be written.
+pub fn write_host(
+    writer: &mut impl Write,
+    host: &ssh::Host,
+    format: ListFormat,
+    fields: &[ssh::HostField],
+    default_port: Option<u16>,
+) -> io::Result<()> {
+    match format {
+        ListFormat::Text => {
+            let fields = if fields.is_empty() {
+                &DEFAULT_TEXT_FIELDS
+            } else {
+                fields
+            };
+            let values = fields
+                .iter()
+                .map(|field| match (field, field.value(host), default_port) {
+                    (ssh::HostField::Port, None, Some(port)) => Cow::Owned(port.to_string()),
+                    (_, value, _) => value.unwrap_or_default(),
+                })
+                .collect::<Vec<_>>();
+            let values = values.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+            writeln!(writer, "{}", values.join("\t"))
+        }
+        ListFormat::Jsonl => {
+            serde_json::to_writer(&mut *writer, host)?;
+            writeln!(writer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_host() {
+        let hosts = ssh_config_hosts("Host web\n  Hostname web.example.com\n  User root\n");
+
+        let mut output = Vec::new();
+        write_host(&mut output, &hosts[0], ListFormat::Text, &[], None).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "web\t\troot\tweb.example.com\t\n"
+        );
+
+        let mut output = Vec::new();
+        let fields = [
+            ssh::HostField::Port,
+            ssh::HostField::Name,
+            ssh::HostField::Destination,
+        ];
+        write_host(&mut output, &hosts[0], ListFormat::Text, &fields, None).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\tweb\tweb.example.com\n"
+        );
+
+        let mut output = Vec::new();
+        write_host(&mut output, &hosts[0], ListFormat::Text, &fields, Some(22)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "22\tweb\tweb.example.com\n"
+        );
+
+        let mut output = Vec::new();
+        write_host(&mut output, &hosts[0], ListFormat::Jsonl, &fields, Some(22)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["name"], "web");
+        assert_eq!(value["user"], "root");
+        assert_eq!(value["destination"], "web.example.com");
+        assert_eq!(value["port"], serde_json::Value::Null);
+    }
+
+    fn ssh_config_hosts(config: &str) -> Vec<ssh::Host> {
+        let path = std::env::temp_dir().join("sshs-test-list-write-host");
+        std::fs::write(&path, config).unwrap();
+        let hosts = ssh::parse_config_file(&path, &crate::ssh_config::Parser::new());
+        std::fs::remove_file(&path).unwrap();
+
+        hosts.unwrap()
+    }
+}
@@ -0,0 +1,163 @@
+//! Diagnostics of the parsing, the include resolution and the merging of the hosts, printed on
+//! stderr for the targets and levels of the `RUST_LOG` environment variable, e.g.
+//! `RUST_LOG=debug` or `RUST_LOG=sshs::ssh_config=trace`. Without it, the warnings of sshs
+//! (e.g. a skipped missing include) are printed.
+//!
+//! The records logged while the user interface owns the terminal are held back and printed once
+//! it is restored, so they never draw over the table.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub const LOG_VARIABLE: &str = "RUST_LOG";
+/// Directives used when `RUST_LOG` isn't set.
+pub const DEFAULT_FILTER: &str = "sshs=warn";
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Levels of the targets, from comma-separated `level`, `target` or `target=level` directives
+/// like `env_logger`'s.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// `None` targets apply to every target.
+    directives: Vec<(Option<String>, LevelFilter)>,
+}
+
+impl Filter {
+    /// Reads the directives, those with an unknown level are left out.
+    #[must_use]
+    pub fn parse(spec: &str) -> Filter {
+        let directives = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .filter_map(|directive| match directive.split_once('=') {
+                Some((target, level)) => Some((Some(target.to_string()), level.parse().ok()?)),
+                None => Some(match directive.parse() {
+                    Ok(level) => (None, level),
+                    Err(_) => (Some(directive.to_string()), LevelFilter::Trace),
+                }),
+            })
+            .collect();
+
+        Filter { directives }
+    }
+
+    /// Level of the most specific directive matching the target, [`LevelFilter::Off`] if none
+    /// matches.
+    #[must_use]
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(directive_target, _)| {
+                directive_target.as_deref().is_none_or(|directive_target| {
+                    target == directive_target
+                        || target
+                            .strip_prefix(directive_target)
+                            .is_some_and(|rest| rest.starts_with("::"))
+                })
+            })
+            .max_by_key(|(directive_target, _)| directive_target.as_ref().map(String::len))
+            .map_or(LevelFilter::Off, |(_, level)| *level)
+    }
+
+    /// Most verbose level of the directives.
+    #[must_use]
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+}
+
+struct Logger {
+    filter: Filter,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!("[{} {}] {}", record.level(), record.target(), record.args());
+        if SUSPENDED.load(Ordering::SeqCst) {
+            if let Ok(mut pending) = PENDING.lock() {
+                pending.push(line);
+                return;
+            }
+        }
+
+        let _ = writeln!(io::stderr(), "{line}");
+    }
+
+    fn flush(&self) {}
+}
+
+/// Starts logging the records `RUST_LOG` asks for, the [`DEFAULT_FILTER`] ones without it.
+pub fn init() {
+    let spec = std::env::var(LOG_VARIABLE).unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+
+    let filter = Filter::parse(&spec);
+    let max_level = filter.max_level();
+    if max_level == LevelFilter::Off {
+        return;
+    }
+
+    if log::set_logger(LOGGER.get_or_init(|| Logger { filter })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Holds the records back until [`resume`], while the terminal shows the user interface.
+pub fn suspend() {
+    SUSPENDED.store(true, Ordering::SeqCst);
+}
+
+/// Prints the records held back since [`suspend`] and the next ones right away.
+pub fn resume() {
+    SUSPENDED.store(false, Ordering::SeqCst);
+
+    if let Ok(mut pending) = PENDING.lock() {
+        let mut stderr = io::stderr().lock();
+        for line in pending.drain(..) {
+            let _ = writeln!(stderr, "{line}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter::parse("warn, sshs::ssh_config=trace,sshs::ssh=off,other,bad=loud");
+
+        assert_eq!(filter.level("sshs"), LevelFilter::Warn);
+        assert_eq!(filter.level("sshs::ssh_config"), LevelFilter::Trace);
+        assert_eq!(filter.level("sshs::ssh_config::parser"), LevelFilter::Trace);
+        assert_eq!(filter.level("sshs::ssh"), LevelFilter::Off);
+        assert_eq!(filter.level("sshs::sshd"), LevelFilter::Warn);
+        assert_eq!(filter.level("other"), LevelFilter::Trace);
+        assert_eq!(filter.level("bad"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+
+        assert_eq!(Filter::parse("").max_level(), LevelFilter::Off);
+        assert_eq!(
+            Filter::parse("sshs=debug").level("ratatui"),
+            LevelFilter::Off
+        );
+    }
+}
@@ -1,41 +1,168 @@
-pub mod searchable;
-pub mod ssh;
-pub mod ssh_config;
-pub mod ui;
-
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clipboard::Clipboard;
+use column_widths::MinColumnWidths;
+use config_file::Document;
+use favorites::Favorites;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use highlight::Highlight;
+use history::History;
+use keybindings::Keybindings;
+use labels::Labels;
+use list::ListFormat;
+use messages::Lang;
+use profiles::Profiles;
+use sshs::{ssh, ssh_config, timing};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
 use ui::{App, AppConfig};
 
+mod browser;
+mod clipboard;
+mod column_widths;
+mod config_file;
+mod favorites;
+mod highlight;
+mod history;
+mod keybindings;
+mod labels;
+mod list;
+mod logger;
+mod messages;
+mod notification;
+mod presets;
+mod profiles;
+mod searchable;
+mod ui;
+mod watcher;
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[allow(clippy::struct_excessive_bools)]
 struct Args {
-    /// Path to the SSH configuration file
-    #[arg(
-        short,
-        long,
-        num_args = 1..,
-        default_values_t = [
-            "/etc/ssh/ssh_config".to_string(),
-            "~/.ssh/config".to_string(),
-        ],
-    )]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the SSH configuration file, `-` reads it from stdin once, reloads reuse it.
+    /// Relative includes of a configuration read from stdin resolve from `~/.ssh`.
+    /// Exactly the given files are read, like `ssh -F`: the default files are only read
+    /// without this option (`/etc/ssh/ssh_config` then `~/.ssh/config`)
+    #[arg(short, long, num_args = 1..)]
     config: Vec<String>,
 
+    /// Read the SSH configuration files of the profile, set in the `[profiles.NAME]` table of
+    /// the configuration file with `paths = [...]`, instead of the `--config` files
+    #[arg(long, value_name = "NAME", conflicts_with = "config")]
+    profile: Option<String>,
+
+    /// Directory of SSH configuration fragments (e.g. `~/.ssh/config.d`) to parse after the
+    /// configuration files, like an `Include PATH/*` line would
+    #[arg(long = "include-dir", value_name = "PATH")]
+    include_dirs: Vec<String>,
+
+    /// Only let `Include` lines include files inside this directory, symlinks resolved.
+    /// Repeatable, the `--include-dir` directories must be inside one of them too
+    #[arg(long = "include-allow-root", value_name = "DIR")]
+    include_allow_roots: Vec<String>,
+
+    /// Also read the system-wide SSH configuration file before the `--config` files
+    #[arg(long, default_value_t = false, conflicts_with = "no_system_config")]
+    system_config: bool,
+
+    /// Don't read the system-wide SSH configuration file, even when it exists or is given
+    /// with `--config`
+    #[arg(long, visible_alias = "user-only", default_value_t = false)]
+    no_system_config: bool,
+
+    /// Also parse the files of `/etc/ssh/ssh_config.d` like an `--include-dir` when the
+    /// system-wide configuration file is read, for systems where it doesn't include them.
+    /// Skipped when the directory doesn't exist
+    #[arg(long, default_value_t = false)]
+    system_include_dir: bool,
+
+    /// Fail on unknown entries and missing included files in the SSH configuration files,
+    /// which are otherwise skipped with a warning
+    #[arg(long, default_value_t = false)]
+    strict: bool,
+
+    /// Show every Host block on its own, without merging the hosts with the same options
+    #[arg(long, default_value_t = false)]
+    no_merge: bool,
+
     /// Shows `ProxyCommand`
     #[arg(long, default_value_t = false)]
     show_proxy_command: bool,
 
-    /// Host search filter
+    /// Shows the jump hosts and proxy commands the selected host is reached through
+    #[arg(long, default_value_t = false)]
+    show_proxy_chain: bool,
+
+    /// Shows `user@destination` in the destination column of the hosts with a user
+    #[arg(long, default_value_t = false)]
+    destination_with_user: bool,
+
+    /// Shows the configuration file each host is defined in
+    #[arg(long, default_value_t = false)]
+    show_source: bool,
+
+    /// Shows the description of each host, the comment ending its `Host` line or else the
+    /// first comment before the first entry of its block
+    #[arg(long, default_value_t = false)]
+    show_description: bool,
+
+    /// Shows the alias matching the search in place of the name when it matches better, e.g.
+    /// for the hosts merged by having the same entries
+    #[arg(long, default_value_t = false)]
+    show_matched_alias: bool,
+
+    /// Port shown in the port column and printed by `--list` in the text format for the hosts
+    /// without a `Port`. Display only: the templates and JSON lines keep the configured port
+    #[arg(long, value_name = "PORT")]
+    default_port: Option<u16>,
+
+    /// Hide hosts without a `HostName`.
+    /// A `HostName` inherited from a wildcard host (e.g. `Host *`) counts as one
+    #[arg(long, default_value_t = false)]
+    require_hostname: bool,
+
+    /// Stop moving up and down at the first and last hosts instead of wrapping around.
+    /// Can also be set with `wrap_around = false` in the configuration file
+    #[arg(long, default_value_t = false)]
+    no_wrap: bool,
+
+    /// Host search filter. `#tag` words only keep the hosts with the tag, and
+    /// `has:<directive>` ones the hosts setting the directive, any SSH configuration directive
+    /// whatever its case (e.g. `has:proxyjump`, `has:identityfile`). `^prefix` words only keep
+    /// the hosts whose name starts with the prefix
     #[arg(short, long)]
     search: Option<String>,
 
-    /// Sort hosts by hostname
-    #[arg(long, default_value_t = true)]
+    /// Host fields matched by the search filter
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = ssh::DEFAULT_SEARCH_FIELDS,
+    )]
+    search_fields: Vec<ssh::HostField>,
+
+    /// Sort hosts by the given field
+    #[arg(long, value_enum, default_value_t = ssh::SortBy::Name)]
+    sort_by: ssh::SortBy,
+
+    /// Direction of the sort, `desc` reverses the order of `--sort-by`
+    #[arg(long, value_enum, default_value_t = ssh::SortDir::Asc)]
+    sort_dir: ssh::SortDir,
+
+    /// Deprecated, hosts are sorted by name by default
+    #[arg(long, hide = true)]
     sort: bool,
 
-    /// Handlebars template of the command to execute
-    #[arg(short, long, default_value = "ssh \"{{{name}}}\"")]
+    /// Handlebars template of the command to execute. Besides the host fields, templates get
+    /// `{{address}}` (`destination:port`, IPv6 addresses in brackets) and `{{home}}`,
+    /// `{{remote_user}}` and `{{local_user}}` like ssh's `%d`, `%r` and `%u` tokens
+    #[arg(short, long, default_value = ssh::DEFAULT_COMMAND_TEMPLATE)]
     template: String,
 
     /// Handlebars template of the command to execute when an SSH session starts
@@ -46,25 +173,729 @@ struct Args {
     #[arg(long, value_name = "TEMPLATE")]
     on_session_end_template: Option<String>,
 
+    /// Handlebars template of a local command to execute after every successful SSH session,
+    /// even with `--exit`
+    #[arg(long, value_name = "TEMPLATE")]
+    after_command: Option<String>,
+
+    /// Where to open the SSH session
+    #[arg(long, value_enum, default_value_t = ssh::Launcher::None)]
+    launcher: ssh::Launcher,
+
+    /// Where the copied host names and destinations go
+    #[arg(long, value_enum, default_value_t = Clipboard::Auto)]
+    clipboard: Clipboard,
+
     /// Exit after ending the SSH session
     #[arg(short, long, default_value_t = false)]
     exit: bool,
+
+    /// Run the sessions in the background and show a desktop notification when they end.
+    /// The commands get no terminal as the hosts list keeps it: only non-interactive templates
+    /// (e.g. opening a new terminal window or running a script) make sense with it. Quitting
+    /// waits for the sessions still running
+    #[arg(long, default_value_t = false, conflicts_with = "exit")]
+    detach: bool,
+
+    /// Gives up connecting after this many seconds: sets `ConnectTimeout` in the commands
+    /// running `ssh` and the `{{connect_timeout}}` template value of every host
+    #[arg(long, value_name = "SECONDS")]
+    connect_timeout: Option<u32>,
+
+    /// Arguments given to ssh after its program in the command connecting to the host, e.g.
+    /// `--ssh-args "-o StrictHostKeyChecking=no -v"`. Commands running another program, like
+    /// `# sshs-command:` overrides running mosh, don't get them
+    #[arg(long = "ssh-args", value_name = "ARGS", allow_hyphen_values = true)]
+    ssh_arguments: Option<String>,
+
+    /// Reloads the hosts when the SSH configuration files or the included files change
+    #[arg(long, default_value_t = false)]
+    watch: bool,
+
+    /// Write the resolved hosts with all their entries as a single SSH configuration file,
+    /// `-` for the standard output, and exit
+    #[arg(long, value_name = "PATH")]
+    export_config: Option<String>,
+
+    /// Print the resolved configuration of the host, like `ssh -G`, and exit.
+    /// Entries from the global options or a pattern host end with `# inherited`
+    #[arg(long, value_name = "HOST")]
+    match_host: Option<String>,
+
+    /// Connect on enter when a single host matches the search, or a single host is named or
+    /// aliased exactly like it, whatever the selected host
+    #[arg(long, default_value_t = false)]
+    enter_on_unique: bool,
+
+    /// Connect to the best match of the search right away, without the user interface
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "search",
+        conflicts_with = "detach"
+    )]
+    first: bool,
+
+    /// Print the rendered command instead of executing it
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Language of the user interface, taken from `LC_ALL`, `LC_MESSAGES` or `LANG` by default
+    #[arg(long, value_enum)]
+    lang: Option<Lang>,
+
+    /// Shows or prints at most N hosts, the best matches when searching
+    #[arg(long, value_name = "N")]
+    max_results: Option<usize>,
+
+    /// Print the hosts matching the search filter and exit
+    #[arg(long, default_value_t = false)]
+    list: bool,
+
+    /// Format of the hosts printed by `--list`, implies `--list`
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    list_format: Option<ListFormat>,
+
+    /// Fields printed by `--list` in the text format and shown as the columns of the table,
+    /// in order (e.g. `name,port,destination`). JSON lines always hold every field
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS")]
+    fields: Vec<ssh::HostField>,
+
+    /// Print on stderr how long parsing the SSH configuration files, applying their patterns and
+    /// merging their hosts took
+    #[arg(long, default_value_t = false)]
+    timing: bool,
+
+    /// Print the supported options, values and SSH configuration entries as JSON and exit
+    #[arg(long, default_value_t = false)]
+    print_capabilities: bool,
+
+    /// Render the command template for an example host like `--template` would, print the
+    /// variables it refers to and exit. Unknown variables are errors
+    #[arg(long, value_name = "TEMPLATE")]
+    check_template: Option<String>,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check SSH configuration files with unknown entries reported as errors, then exit with 1
+    /// if any file has an error
+    Validate {
+        /// Files to check, the `--config` files or the default ones when not given
+        paths: Vec<String>,
+    },
+}
+
+impl Args {
+    /// Paths of the SSH configuration files to read, in order.
+    fn config_paths(&self) -> Vec<String> {
+        let mut config_paths = if self.config.is_empty() {
+            vec![
+                ssh::SYSTEM_CONFIG_PATH.to_string(),
+                ssh::USER_CONFIG_PATH.to_string(),
+            ]
+        } else if self.system_config {
+            std::iter::once(ssh::SYSTEM_CONFIG_PATH.to_string())
+                .chain(self.config.iter().cloned())
+                .collect()
+        } else {
+            self.config.clone()
+        };
+
+        if self.no_system_config {
+            config_paths.retain(|path| path != ssh::SYSTEM_CONFIG_PATH);
+        }
+
+        config_paths
+    }
+
+    /// Directories the included files must be in, any file can be included when empty.
+    fn include_allow_roots(&self) -> Vec<PathBuf> {
+        self.include_allow_roots
+            .iter()
+            .map(|root| ssh::expand_path(root))
+            .collect()
+    }
+
+    /// Arguments of `--ssh-args`, split like shell words.
+    fn ssh_args(&self) -> Result<Vec<String>> {
+        let Some(ssh_args) = &self.ssh_arguments else {
+            return Ok(Vec::new());
+        };
+
+        shlex::split(ssh_args).with_context(|| format!("Failed to parse --ssh-args: {ssh_args}"))
+    }
+
+    /// Directories whose files are parsed after the configuration files, in order.
+    fn include_dirs(&self, config_paths: &[String]) -> Vec<String> {
+        let system_include_dir = ssh::SYSTEM_INCLUDE_DIR
+            .filter(|_| self.system_include_dir)
+            .filter(|_| {
+                config_paths
+                    .iter()
+                    .any(|path| path == ssh::SYSTEM_CONFIG_PATH)
+            })
+            .filter(|dir| Path::new(dir).is_dir());
+
+        system_include_dir
+            .map(str::to_string)
+            .into_iter()
+            .chain(self.include_dirs.iter().cloned())
+            .collect()
+    }
+}
+
+/// Names of the possible values of a `ValueEnum`, as written on the command line.
+fn value_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
 
-    let mut app = App::new(&AppConfig {
+/// Describes what this version of sshs supports, for wrapper scripts.
+///
+/// Doesn't read any configuration.
+fn capabilities() -> serde_json::Value {
+    let flags = Args::command()
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| arg.get_long().map(str::to_string))
+        .collect::<Vec<_>>();
+
+    let entry_types = ssh_config::EntryType::iter()
+        .filter(|entry_type| !matches!(entry_type, ssh_config::EntryType::Unknown(_)))
+        .map(|entry_type| entry_type.to_string())
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "flags": flags,
+        "search_fields": value_names::<ssh::HostField>(),
+        "sort_by": value_names::<ssh::SortBy>(),
+        "sort_dir": value_names::<ssh::SortDir>(),
+        "launchers": value_names::<ssh::Launcher>(),
+        "list_formats": value_names::<ListFormat>(),
+        "languages": value_names::<Lang>(),
+        "keybinding_actions": Keybindings::action_names(),
+        "entry_types": entry_types,
+        "features": {
+            "signal_handling": cfg!(unix),
+            "notifications": cfg!(not(windows)),
+        },
+    })
+}
+
+/// Settings of the [`config_file::CONFIG_FILE_NAME`] file.
+struct Settings {
+    keybindings: Keybindings,
+    highlight: Highlight,
+    min_column_widths: MinColumnWidths,
+    command_template_presets: Vec<(String, String)>,
+    wrap_around: Option<bool>,
+    tags_annotation_prefix: Option<String>,
+}
+
+/// Configuration files of the profile, see `--profile`.
+fn profile_paths(name: &str) -> Result<Vec<String>> {
+    let path = config_file::config_dir().join(config_file::CONFIG_FILE_NAME);
+    let context = || format!("Failed to load {}", path.display());
+
+    let document = Document::load(&path)
+        .with_context(context)?
+        .unwrap_or_default();
+    let profiles = Profiles::from_document(&document).with_context(context)?;
+
+    let Some(paths) = profiles.get(name) else {
+        let names = profiles.names().collect::<Vec<_>>();
+        if names.is_empty() {
+            anyhow::bail!(
+                "Unknown profile `{name}`, no profiles are set in {}",
+                path.display()
+            );
+        }
+        anyhow::bail!(
+            "Unknown profile `{name}`, the profiles are {}",
+            names.join(", ")
+        );
+    };
+
+    Ok(paths.to_vec())
+}
+
+impl Settings {
+    fn load() -> Result<Settings> {
+        let path = config_file::config_dir().join(config_file::CONFIG_FILE_NAME);
+        let context = || format!("Failed to load {}", path.display());
+
+        let document = Document::load(&path)
+            .with_context(context)?
+            .unwrap_or_default();
+
+        let (keybindings, unknown_actions) =
+            Keybindings::from_document(&document).with_context(context)?;
+        if !unknown_actions.is_empty() {
+            eprintln!(
+                "Ignoring unknown keybinding actions in {}: {} (known actions: {})",
+                path.display(),
+                unknown_actions.join(", "),
+                Keybindings::action_names().join(", "),
+            );
+        }
+
+        let tags_annotation_prefix = document.get_str("tags_annotation").with_context(context)?;
+        if tags_annotation_prefix.is_some_and(|prefix| prefix.trim().is_empty()) {
+            anyhow::bail!("{}: `tags_annotation` must not be empty", context());
+        }
+
+        Ok(Settings {
+            keybindings,
+            highlight: Highlight::from_document(&document).with_context(context)?,
+            min_column_widths: MinColumnWidths::from_document(&document).with_context(context)?,
+            command_template_presets: presets::from_document(&document).with_context(context)?,
+            wrap_around: document.get_bool("wrap_around").with_context(context)?,
+            tags_annotation_prefix: tags_annotation_prefix.map(str::to_string),
+        })
+    }
+}
+
+/// Prints the entries resolved for the host like `ssh -G` would, see `--match-host`.
+fn print_host_entries(args: &Args, name: &str) -> Result<()> {
+    let parser = ssh_config::Parser::new()
+        .ignore_unknown_entries(!args.strict)
+        .ignore_missing_includes(!args.strict)
+        .include_allow_roots(&args.include_allow_roots());
+    let Some(entries) = ssh::resolve_host_entries(&args.config, &args.include_dirs, &parser, name)?
+    else {
+        anyhow::bail!("Host `{name}` not found");
+    };
+
+    for (key, value, is_inherited) in entries {
+        if is_inherited {
+            println!("{key} {value} # inherited");
+        } else {
+            println!("{key} {value}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the resolved hosts as a single SSH configuration file, see `--export-config`.
+fn export_config(args: &Args, path: &str) -> Result<()> {
+    let parser = ssh_config::Parser::new()
+        .ignore_unknown_entries(!args.strict)
+        .ignore_missing_includes(!args.strict)
+        .merge_same_hosts(!args.no_merge)
+        .include_allow_roots(&args.include_allow_roots());
+    let hosts = ssh::parse_configs_entries(&args.config, &args.include_dirs, &parser)?;
+
+    // Written in memory first so a host that can't be exported leaves no partial file
+    let mut written = Vec::new();
+    ssh_config::writer::write_hosts(&mut written, &hosts).context("Failed to export")?;
+
+    if path == "-" {
+        return Ok(io::stdout().lock().write_all(&written)?);
+    }
+
+    std::fs::write(path, written).with_context(|| format!("Failed to write {path}"))
+}
+
+/// Parses each SSH configuration file in strict mode and prints its errors.
+///
+/// Returns `true` if every file is valid.
+fn validate(paths: &[String], include_dirs: &[String], include_allow_roots: &[PathBuf]) -> bool {
+    let parser = ssh_config::Parser::new()
+        .ignore_unknown_entries(false)
+        .ignore_missing_includes(false)
+        .include_allow_roots(include_allow_roots);
+    let mut is_valid = true;
+
+    for path in paths {
+        for result in ssh::parse_configs(std::slice::from_ref(path), &[], &parser) {
+            match result {
+                Ok(_) => println!("{path}: ok"),
+                // The other errors already tell the file and the line
+                Err(err @ ssh::ParseConfigError::Io(_)) => {
+                    is_valid = false;
+                    eprintln!("{path}: {err}");
+                }
+                Err(err) => {
+                    is_valid = false;
+                    eprintln!("{err}");
+                }
+            }
+        }
+    }
+
+    for dir in include_dirs {
+        for result in ssh::parse_configs(&[], std::slice::from_ref(dir), &parser) {
+            if let Err(err) = result {
+                is_valid = false;
+                eprintln!("{dir}: {err}");
+            }
+        }
+    }
+
+    is_valid
+}
+
+/// Prints the variables of the template and the command rendered for an example host, the
+/// errors on stderr, and returns `false` if the template is invalid.
+fn check_template(template: &str) -> bool {
+    let variables = match ssh::TemplateContext::referenced_variables(template) {
+        Ok(variables) => variables,
+        Err(err) => {
+            eprintln!("Invalid template: {err}");
+            return false;
+        }
+    };
+    println!("Variables: {}", variables.join(", "));
+
+    let mut host = ssh::Host::ad_hoc("example.com").unwrap_or_else(|| unreachable!());
+    host.user = Some("admin".to_string());
+    host.port = Some("2222".to_string());
+
+    match host.render_command_template(template, ssh::Launcher::None, &[]) {
+        Ok((rendered_command, args)) => {
+            println!("Rendered for admin@example.com:2222: {rendered_command}");
+            println!("  argv: {args:?}");
+            true
+        }
+        Err(err) => {
+            eprintln!("Invalid template: {err}");
+            false
+        }
+    }
+}
+
+/// Checks the command templates at startup rather than when connecting to a host.
+fn validate_templates(args: &Args, presets: &[(String, String)]) -> Result<()> {
+    let options = [
+        ("--template", Some(&args.template)),
+        (
+            "--on-session-start-template",
+            args.on_session_start_template.as_ref(),
+        ),
+        (
+            "--on-session-end-template",
+            args.on_session_end_template.as_ref(),
+        ),
+        ("--after-command", args.after_command.as_ref()),
+    ];
+    for (option, template) in options {
+        if let Some(template) = template {
+            ssh::TemplateContext::validate(template)
+                .with_context(|| format!("Invalid {option}"))?;
+        }
+    }
+
+    for (name, template) in presets {
+        ssh::TemplateContext::validate(template)
+            .with_context(|| format!("Invalid preset `{name}`"))?;
+    }
+
+    Ok(())
+}
+
+/// Prints the hosts like the table would list them.
+///
+/// Hosts are written as soon as their configuration file is parsed when they aren't sorted.
+fn list(args: &Args, tags_annotation_prefix: Option<&str>, labels: &Labels) -> Result<()> {
+    let mut parser = ssh_config::Parser::new()
+        .ignore_unknown_entries(!args.strict)
+        .ignore_missing_includes(!args.strict)
+        .merge_same_hosts(!args.no_merge)
+        .include_allow_roots(&args.include_allow_roots());
+    if let Some(prefix) = tags_annotation_prefix {
+        parser = parser.tags_annotation_prefix(prefix);
+    }
+    let matcher = SkimMatcherV2::default();
+    let search = args.search.clone().unwrap_or_default();
+    let format = args.list_format.unwrap_or_default();
+
+    let max_results = args.max_results.unwrap_or(usize::MAX);
+
+    let mut stdout = io::stdout().lock();
+    let mut sorted_hosts = Vec::new();
+    let mut written = 0;
+
+    for hosts in ssh::parse_configs(&args.config, &args.include_dirs, &parser) {
+        let hosts = hosts.context("Failed to parse SSH configuration file")?;
+
+        for mut host in hosts {
+            if args.require_hostname && !host.has_explicit_hostname {
+                continue;
+            }
+
+            host.label = labels.find(&host.name).and_then(|label| label.text.clone());
+            if !host.matches_search(&matcher, &args.search_fields, &search) {
+                continue;
+            }
+
+            if args.sort_by == ssh::SortBy::None && args.sort_dir == ssh::SortDir::Asc {
+                if written == max_results {
+                    return Ok(());
+                }
+                list::write_host(&mut stdout, &host, format, &args.fields, args.default_port)?;
+                written += 1;
+            } else {
+                sorted_hosts.push(host);
+            }
+        }
+    }
+
+    args.sort_by.sort(&mut sorted_hosts, args.sort_dir);
+    for host in sorted_hosts.iter().take(max_results) {
+        list::write_host(&mut stdout, host, format, &args.fields, args.default_port)?;
+    }
+
+    Ok(())
+}
+
+/// Configuration of the user interface from the arguments and sshs' configuration files.
+fn app_config(args: Args, settings: Settings, labels: Labels) -> Result<AppConfig> {
+    let search_history_path = History::default_path();
+    let search_history = History::load(&search_history_path)
+        .with_context(|| format!("Failed to load {}", search_history_path.display()))?;
+
+    let favorites_path = Favorites::default_path();
+    let favorites = Favorites::load(&favorites_path)
+        .with_context(|| format!("Failed to load {}", favorites_path.display()))?;
+
+    let include_allow_roots = args.include_allow_roots();
+    let ssh_args = args.ssh_args()?;
+
+    Ok(AppConfig {
         config_paths: args.config,
+        include_dirs: args.include_dirs,
+        include_allow_roots,
+        strict: args.strict,
+        merge_same_hosts: !args.no_merge,
+        tags_annotation_prefix: settings.tags_annotation_prefix,
         search_filter: args.search,
-        sort_by_name: args.sort,
+        search_fields: args.search_fields,
+        sort_by: args.sort_by,
+        sort_dir: args.sort_dir,
+        require_hostname: args.require_hostname,
+        no_wrap: args.no_wrap || settings.wrap_around == Some(false),
         show_proxy_command: args.show_proxy_command,
+        show_proxy_chain: args.show_proxy_chain,
+        show_source: args.show_source,
+        show_description: args.show_description,
+        show_matched_alias: args.show_matched_alias,
+        destination_with_user: args.destination_with_user,
+        default_port: args.default_port,
+        columns: args.fields.clone(),
+        labels,
+        keybindings: settings.keybindings,
+        highlight: settings.highlight,
+        min_column_widths: settings.min_column_widths,
+        search_history,
+        favorites,
         command_template: args.template,
+        command_template_presets: settings.command_template_presets,
         command_template_on_session_start: args.on_session_start_template,
         command_template_on_session_end: args.on_session_end_template,
+        after_command: args.after_command,
+        launcher: args.launcher,
+        ssh_args,
+        clipboard: args.clipboard,
         exit_after_ssh_session_ends: args.exit,
-    })?;
-    app.start()?;
+        dry_run: args.dry_run,
+        enter_on_unique: args.enter_on_unique,
+        detach: args.detach,
+        watch: args.watch,
+        connect_timeout: args.connect_timeout,
+        max_results: args.max_results,
+        lang: args.lang.unwrap_or_else(Lang::from_env),
+    })
+}
+
+fn main() -> Result<()> {
+    let mut args = Args::parse();
+    logger::init();
+
+    if args.print_capabilities {
+        println!("{}", capabilities());
+        return Ok(());
+    }
+
+    if let Some(template) = &args.check_template {
+        if !check_template(template) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = &args.profile {
+        args.config = profile_paths(name)?;
+    }
+    if let Some(Command::Validate { paths }) = &args.command {
+        if !paths.is_empty() {
+            args.config.clone_from(paths);
+        }
+    }
+    args.config = args.config_paths();
+    if args.timing {
+        timing::enable();
+    }
+
+    args.include_dirs.retain(|dir| {
+        let is_dir = ssh::expand_path(dir).is_dir();
+        if !is_dir {
+            eprintln!("Skipping the include directory {dir}, it isn't a directory");
+        }
+        is_dir
+    });
+    args.include_dirs = args.include_dirs(&args.config);
+
+    if let Some(Command::Validate { .. }) = args.command {
+        if !validate(
+            &args.config,
+            &args.include_dirs,
+            &args.include_allow_roots(),
+        ) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = &args.match_host {
+        let result = print_host_entries(&args, name);
+        timing::report();
+        return result;
+    }
+
+    if let Some(path) = &args.export_config {
+        let result = export_config(&args, path);
+        timing::report();
+        return result;
+    }
+
+    let settings = Settings::load()?;
+
+    let labels_path = Labels::default_path();
+    let labels = Labels::load(&labels_path)
+        .with_context(|| format!("Failed to load {}", labels_path.display()))?;
+
+    if args.list || args.list_format.is_some() {
+        let result = list(&args, settings.tags_annotation_prefix.as_deref(), &labels);
+        timing::report();
+        return match result {
+            // The output was closed early, e.g. piped into `head`
+            Err(err)
+                if err
+                    .downcast_ref::<io::Error>()
+                    .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+            {
+                Ok(())
+            }
+            result => result,
+        };
+    }
+
+    validate_templates(&args, &settings.command_template_presets)?;
+
+    let first = args.first;
+    let mut app = App::new(&app_config(args, settings, labels)?)?;
+    // Printed before the user interface takes the terminal
+    timing::report();
+    let exit_code = if first {
+        app.connect_first()?
+    } else {
+        app.start()?
+    };
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_paths(args: &[&str]) -> Vec<String> {
+        Args::try_parse_from(std::iter::once("sshs").chain(args.iter().copied()))
+            .unwrap()
+            .config_paths()
+    }
+
+    #[test]
+    fn test_include_dirs() {
+        let include_dirs = |args: &[&str]| {
+            let args =
+                Args::try_parse_from(std::iter::once("sshs").chain(args.iter().copied())).unwrap();
+            args.include_dirs(&args.config_paths())
+        };
+
+        assert!(include_dirs(&[]).is_empty());
+        assert_eq!(include_dirs(&["--include-dir", "a"]), ["a"]);
+        assert_eq!(
+            include_dirs(&["--system-include-dir", "--user-only", "--include-dir", "a"]),
+            ["a"]
+        );
+
+        let system_include_dir = ssh::SYSTEM_INCLUDE_DIR.filter(|dir| Path::new(dir).is_dir());
+        assert_eq!(
+            include_dirs(&["--system-include-dir"]),
+            system_include_dir.into_iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_config_paths() {
+        assert_eq!(
+            config_paths(&[]),
+            [ssh::SYSTEM_CONFIG_PATH, ssh::USER_CONFIG_PATH]
+        );
+        assert_eq!(config_paths(&["--user-only"]), [ssh::USER_CONFIG_PATH]);
+        assert_eq!(config_paths(&["-c", "a", "b"]), ["a", "b"]);
+        assert_eq!(
+            config_paths(&["-c", "a", "--system-config"]),
+            [ssh::SYSTEM_CONFIG_PATH, "a"]
+        );
+        assert_eq!(
+            config_paths(&["-c", ssh::SYSTEM_CONFIG_PATH, "a", "--no-system-config"]),
+            ["a"]
+        );
+        assert!(Args::try_parse_from(["sshs", "--system-config", "--no-system-config"]).is_err());
+    }
+
+    #[test]
+    fn test_first() {
+        assert!(Args::try_parse_from(["sshs", "--first"]).is_err());
+        assert!(
+            Args::try_parse_from(["sshs", "--first", "-s", "web"])
+                .unwrap()
+                .first
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let directory = std::env::temp_dir().join("sshs-test-validate");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+        std::fs::write(path("valid"), "Host web\n  HostName web.example.com\n").unwrap();
+        std::fs::write(path("unknown"), "Host web\n  Unknown entry\n").unwrap();
+
+        let valid = validate(&[path("valid")], &[], &[]);
+        let unknown = validate(&[path("valid"), path("unknown")], &[], &[]);
+        let missing = validate(&[path("missing")], &[], &[]);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(valid);
+        assert!(!unknown);
+        assert!(!missing);
+        assert!(matches!(
+            Args::try_parse_from(["sshs", "validate", "a", "b"]).unwrap().command,
+            Some(Command::Validate { paths }) if paths == ["a", "b"]
+        ));
+    }
+}
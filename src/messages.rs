@@ -0,0 +1,332 @@
+//! Strings shown in the user interface, in the language picked with `--lang` or the locale.
+//!
+//! Messages taking arguments hold a `{}` placeholder per argument, filled by [`format`].
+
+use clap::ValueEnum;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Lang {
+    /// Language of a locale like `fr_FR.UTF-8`, `None` if there is no catalog for it.
+    #[must_use]
+    pub fn from_locale(locale: &str) -> Option<Lang> {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match language.as_str() {
+            "c" | "posix" | "en" => Some(Lang::En),
+            "fr" => Some(Lang::Fr),
+            _ => None,
+        }
+    }
+
+    /// Language of the first locale set in `LC_ALL`, `LC_MESSAGES` or `LANG`, English by default.
+    #[must_use]
+    pub fn from_env() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .and_then(|locale| Lang::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Lang::En => &EN,
+            Lang::Fr => &FR,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Messages {
+    pub any_key: &'static str,
+    pub close: &'static str,
+    pub cancel: &'static str,
+    pub quit: &'static str,
+    pub move_up: &'static str,
+    pub move_down: &'static str,
+    pub select: &'static str,
+    pub retry: &'static str,
+    pub open_in_browser: &'static str,
+    pub show_proxy: &'static str,
+    pub hide_proxy: &'static str,
+    pub show_proxy_chain: &'static str,
+    pub hide_proxy_chain: &'static str,
+    pub sort_ascending: &'static str,
+    pub sort_descending: &'static str,
+    pub expand_aliases: &'static str,
+    pub collapse_aliases: &'static str,
+    pub copy_name: &'static str,
+    pub copy_destination: &'static str,
+    pub copy_config: &'static str,
+    pub add_favorite: &'static str,
+    pub remove_favorite: &'static str,
+    pub add_to_selection: &'static str,
+    pub remove_from_selection: &'static str,
+    /// Host name.
+    pub connect_to: &'static str,
+    /// Host name.
+    pub connect_to_ad_hoc: &'static str,
+    /// Number of selected hosts.
+    pub connect_to_selection: &'static str,
+    /// Preset name.
+    pub preset: &'static str,
+
+    /// Host name, exit code.
+    pub last_session_exited: &'static str,
+    /// Host name.
+    pub last_session_terminated: &'static str,
+    /// Copied text.
+    pub copied: &'static str,
+    /// Host name.
+    pub copied_config: &'static str,
+    /// Error.
+    pub copy_failed: &'static str,
+    /// URL.
+    pub opened: &'static str,
+    /// URL, error.
+    pub open_failed: &'static str,
+    /// Host name, tag.
+    pub not_tagged: &'static str,
+    /// Error.
+    pub search_history_save_failed: &'static str,
+    /// Error.
+    pub favorites_save_failed: &'static str,
+    /// Host name.
+    pub detached: &'static str,
+    /// Number of sessions.
+    pub waiting_for_detached_sessions: &'static str,
+    pub reloaded: &'static str,
+    /// Number of hosts shown, number of matching hosts.
+    pub showing_results: &'static str,
+    /// Error.
+    pub reload_failed: &'static str,
+
+    /// Host name.
+    pub connecting: &'static str,
+    /// Host name.
+    pub session_ended: &'static str,
+    /// Host name, exit code.
+    pub session_exited: &'static str,
+    /// Host name.
+    pub session_terminated: &'static str,
+    /// Host name, error.
+    pub session_failed: &'static str,
+
+    pub proxy_chain: &'static str,
+    pub direct_connection: &'static str,
+    pub not_configured: &'static str,
+    /// Jump host.
+    pub proxy_cycle: &'static str,
+
+    pub no_hosts: &'static str,
+    /// Search.
+    pub no_matches: &'static str,
+
+    pub terminal_too_small: &'static str,
+    /// Minimum width, minimum height.
+    pub terminal_needs: &'static str,
+
+    pub dry_run: &'static str,
+    pub on_session_start: &'static str,
+    pub command: &'static str,
+    pub on_session_end: &'static str,
+    pub after_command: &'static str,
+}
+
+pub const EN: Messages = Messages {
+    any_key: "any key",
+    close: "close",
+    cancel: "cancel",
+    quit: "quit",
+    move_up: "move up",
+    move_down: "move down",
+    select: "select",
+    retry: "retry",
+    open_in_browser: "open in browser",
+    show_proxy: "show proxy",
+    hide_proxy: "hide proxy",
+    show_proxy_chain: "show proxy chain",
+    hide_proxy_chain: "hide proxy chain",
+    sort_ascending: "sort ascending",
+    sort_descending: "sort descending",
+    expand_aliases: "expand aliases",
+    collapse_aliases: "collapse aliases",
+    copy_name: "copy name",
+    copy_destination: "copy user@destination",
+    copy_config: "copy the resolved configuration",
+    add_favorite: "pin",
+    remove_favorite: "unpin",
+    add_to_selection: "add to selection",
+    remove_from_selection: "remove from selection",
+    connect_to: "connect to {}",
+    connect_to_ad_hoc: "connect to {} (ad-hoc)",
+    connect_to_selection: "connect to the {} selected hosts",
+    preset: "preset: {}",
+
+    last_session_exited: "last: {} exited {}",
+    last_session_terminated: "last: {} was terminated",
+    copied: "copied {}",
+    copied_config: "copied the configuration of {}",
+    copy_failed: "failed to copy: {}",
+    opened: "opened {}",
+    open_failed: "failed to open {}: {}",
+    not_tagged: "{} isn't tagged `#sshs {}`",
+    search_history_save_failed: "failed to save the search history: {}",
+    favorites_save_failed: "failed to save the favorites: {}",
+    detached: "{} started in the background",
+    waiting_for_detached_sessions:
+        "Waiting for the {} sessions running in the background to end, ctrl+c stops them",
+    reloaded: "configuration reloaded",
+    showing_results: "showing {} of {}",
+    reload_failed: "failed to reload the configuration: {}",
+
+    connecting: "Connecting to {}",
+    session_ended: "{} ended",
+    session_exited: "{} exited {}",
+    session_terminated: "{} was terminated",
+    session_failed: "{} failed: {}",
+
+    proxy_chain: "Proxy chain",
+    direct_connection: "direct connection",
+    not_configured: "not in the configuration",
+    proxy_cycle: "{} again, the chain loops",
+
+    no_hosts: "No hosts found",
+    no_matches: "No matches for '{}'",
+
+    terminal_too_small: "Terminal too small",
+    terminal_needs: "needs {}x{}",
+
+    dry_run: "Dry run",
+    on_session_start: "on session start",
+    command: "command",
+    on_session_end: "on session end",
+    after_command: "after command",
+};
+
+pub const FR: Messages = Messages {
+    any_key: "toute touche",
+    close: "fermer",
+    cancel: "annuler",
+    quit: "quitter",
+    move_up: "monter",
+    move_down: "descendre",
+    select: "sélectionner",
+    retry: "réessayer",
+    open_in_browser: "ouvrir dans le navigateur",
+    show_proxy: "afficher le proxy",
+    hide_proxy: "masquer le proxy",
+    show_proxy_chain: "afficher la chaîne de proxys",
+    hide_proxy_chain: "masquer la chaîne de proxys",
+    sort_ascending: "tri croissant",
+    sort_descending: "tri décroissant",
+    expand_aliases: "développer les alias",
+    collapse_aliases: "réduire les alias",
+    copy_name: "copier le nom",
+    copy_destination: "copier utilisateur@destination",
+    copy_config: "copier la configuration résolue",
+    add_favorite: "épingler",
+    remove_favorite: "désépingler",
+    add_to_selection: "ajouter à la sélection",
+    remove_from_selection: "retirer de la sélection",
+    connect_to: "se connecter à {}",
+    connect_to_ad_hoc: "se connecter à {} (ad hoc)",
+    connect_to_selection: "se connecter aux {} hôtes sélectionnés",
+    preset: "préréglage : {}",
+
+    last_session_exited: "dernière : {} a quitté avec {}",
+    last_session_terminated: "dernière : {} a été interrompue",
+    copied: "{} copié",
+    copied_config: "configuration de {} copiée",
+    copy_failed: "échec de la copie : {}",
+    opened: "{} ouvert",
+    open_failed: "échec de l'ouverture de {} : {}",
+    not_tagged: "{} n'a pas le tag `#sshs {}`",
+    search_history_save_failed: "échec de l'enregistrement de l'historique de recherche : {}",
+    favorites_save_failed: "échec de l'enregistrement des favoris : {}",
+    detached: "{} démarré en arrière-plan",
+    waiting_for_detached_sessions:
+        "Attente de la fin des {} sessions en arrière-plan, ctrl+c les arrête",
+    reloaded: "configuration rechargée",
+    showing_results: "{} affichés sur {}",
+    reload_failed: "échec du rechargement de la configuration : {}",
+
+    connecting: "Connexion à {}",
+    session_ended: "{} terminé",
+    session_exited: "{} a quitté avec {}",
+    session_terminated: "{} a été interrompu",
+    session_failed: "échec de {} : {}",
+
+    proxy_chain: "Chaîne de proxys",
+    direct_connection: "connexion directe",
+    not_configured: "absent de la configuration",
+    proxy_cycle: "{} à nouveau, la chaîne boucle",
+
+    no_hosts: "Aucun hôte trouvé",
+    no_matches: "Aucun résultat pour '{}'",
+
+    terminal_too_small: "Terminal trop petit",
+    terminal_needs: "{}x{} requis",
+
+    dry_run: "Simulation",
+    on_session_start: "au début de la session",
+    command: "commande",
+    on_session_end: "à la fin de la session",
+    after_command: "après la commande",
+};
+
+/// Replaces each `{}` placeholder of the message with the next argument.
+#[must_use]
+pub fn format(message: &str, args: &[&dyn Display]) -> String {
+    let mut parts = message.split("{}");
+    let mut formatted = parts.next().unwrap_or_default().to_string();
+
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            formatted.push_str(&arg.to_string());
+        }
+        formatted.push_str(part);
+    }
+
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_locale() {
+        assert_eq!(Lang::from_locale("fr_FR.UTF-8"), Some(Lang::Fr));
+        assert_eq!(Lang::from_locale("fr"), Some(Lang::Fr));
+        assert_eq!(Lang::from_locale("en_US"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("C.UTF-8"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("de_DE.UTF-8"), None);
+        assert_eq!(Lang::from_locale(""), None);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format(EN.connect_to, &[&"web"]), "connect to web");
+        assert_eq!(
+            format(EN.last_session_exited, &[&"web", &255]),
+            "last: web exited 255"
+        );
+        assert_eq!(format(EN.quit, &[&"ignored"]), "quit");
+        assert_eq!(format(EN.open_failed, &[&"url"]), "failed to open url: ");
+    }
+}
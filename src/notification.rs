@@ -0,0 +1,53 @@
+use std::process::{Command, Stdio};
+
+/// Command showing a desktop notification.
+fn notify_command<'a>(summary: &'a str, body: &'a str) -> Option<(&'static str, Vec<&'a str>)> {
+    if cfg!(target_os = "macos") {
+        // Passed as arguments so they don't need to be escaped in the script
+        return Some((
+            "osascript",
+            vec![
+                "-e",
+                "on run argv",
+                "-e",
+                "display notification (item 2 of argv) with title (item 1 of argv)",
+                "-e",
+                "end run",
+                summary,
+                body,
+            ],
+        ));
+    }
+
+    if cfg!(windows) {
+        return None;
+    }
+
+    Some(("notify-send", vec!["--app-name=sshs", summary, body]))
+}
+
+/// Shows a desktop notification.
+///
+/// # Errors
+///
+/// Will return `Err` if notifications aren't supported, the notification command cannot be found
+/// or if it fails.
+pub fn send(summary: &str, body: &str) -> anyhow::Result<()> {
+    let Some((command, args)) = notify_command(summary, body) else {
+        anyhow::bail!("Desktop notifications aren't supported on this platform");
+    };
+
+    let status = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run {command}: {e}"))?;
+
+    if !status.success() {
+        anyhow::bail!("{command} exited with {status}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Named command templates, configured in the `[presets]` table of the
+//! [`CONFIG_FILE_NAME`](crate::config_file::CONFIG_FILE_NAME) file:
+//!
+//! ```toml
+//! [presets]
+//! mosh = "mosh {{{name}}}"
+//! tmux = "ssh -t \"{{{name}}}\" tmux new -A -s main"
+//! ```
+//!
+//! The `--template` command comes first as the `default` preset.
+
+use crate::config_file::{ConfigFileError, Document, Value};
+
+pub const PRESETS_TABLE: &str = "presets";
+
+/// Name of the preset holding the `--template` command.
+pub const DEFAULT_PRESET_NAME: &str = "default";
+
+/// Reads the `[presets]` table as `(name, template)` pairs, in order.
+///
+/// # Errors
+///
+/// Will return `Err` if a preset isn't a valid Handlebars template.
+pub fn from_document(document: &Document) -> Result<Vec<(String, String)>, ConfigFileError> {
+    let Some(table) = document.table(&[PRESETS_TABLE]) else {
+        return Ok(Vec::new());
+    };
+
+    let invalid = |message: String| ConfigFileError::Invalid {
+        table: PRESETS_TABLE.to_string(),
+        message,
+    };
+
+    table
+        .get_entries()
+        .iter()
+        .map(|(name, value)| {
+            let Value::String(template) = value else {
+                return Err(invalid(format!("`{name}` must be a template string")));
+            };
+
+            handlebars::Template::compile(template)
+                .map_err(|e| invalid(format!("`{name}` is an invalid template: {e}")))?;
+
+            Ok((name.clone(), template.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_document() {
+        let document =
+            Document::parse("[presets]\nmosh = \"mosh {{{name}}}\"\nplain = 'ssh {{name}}'\n")
+                .unwrap();
+
+        assert_eq!(
+            from_document(&document).unwrap(),
+            [
+                ("mosh".to_string(), "mosh {{{name}}}".to_string()),
+                ("plain".to_string(), "ssh {{name}}".to_string()),
+            ]
+        );
+
+        let document = Document::parse("[presets]\nbroken = \"ssh {{#if}}\"\n").unwrap();
+        assert!(from_document(&document).is_err());
+
+        let document = Document::parse("[presets]\nnumber = 1\n").unwrap();
+        assert!(from_document(&document).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+//! Named sets of SSH configuration files picked with `--profile`, configured in the
+//! `[profiles.<name>]` tables of the [`CONFIG_FILE_NAME`](crate::config_file::CONFIG_FILE_NAME)
+//! file:
+//!
+//! ```toml
+//! [profiles.work]
+//! paths = ["~/.ssh/config.work"]
+//!
+//! [profiles.personal]
+//! paths = ["~/.ssh/config.personal", "~/.ssh/config.d/home"]
+//! ```
+//!
+//! Exactly the files of the profile are read, like with `--config`.
+
+use crate::config_file::{ConfigFileError, Document, Value};
+
+pub const PROFILES_TABLE: &str = "profiles";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profiles {
+    profiles: Vec<(String, Vec<String>)>,
+}
+
+impl Profiles {
+    /// Reads the `[profiles.<name>]` tables, in order.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a profile sets another key than `paths`, or if its `paths` aren't
+    /// a non-empty array of strings.
+    pub fn from_document(document: &Document) -> Result<Profiles, ConfigFileError> {
+        let mut profiles = Profiles::default();
+
+        for table in document.tables() {
+            let [table_name, name] = table.get_name() else {
+                continue;
+            };
+            if table_name != PROFILES_TABLE {
+                continue;
+            }
+
+            let invalid = |message: String| ConfigFileError::Invalid {
+                table: format!("{PROFILES_TABLE}.{name}"),
+                message,
+            };
+
+            if let Some((key, _)) = table.get_entries().iter().find(|(key, _)| key != "paths") {
+                return Err(invalid(format!(
+                    "unknown key `{key}`, only `paths` is allowed"
+                )));
+            }
+
+            let paths = table
+                .get("paths")
+                .and_then(Value::as_array)
+                .filter(|paths| !paths.is_empty())
+                .and_then(|paths| {
+                    paths
+                        .iter()
+                        .map(|path| Value::as_str(path).map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| {
+                    invalid("`paths` must be a non-empty array of strings".to_string())
+                })?;
+
+            profiles.profiles.push((name.clone(), paths));
+        }
+
+        Ok(profiles)
+    }
+
+    /// Configuration files of the profile, `None` if there is no profile with this name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.profiles
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, paths)| paths.as_slice())
+    }
+
+    /// Names of the profiles, in the order they are configured.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.iter().map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_document() {
+        let profiles = |config: &str| Profiles::from_document(&Document::parse(config).unwrap());
+
+        let loaded = profiles(
+            "[presets]\nmosh = \"mosh {{{name}}}\"\n\n\
+             [profiles.work]\npaths = [\"~/.ssh/config.work\"]\n\n\
+             [profiles.personal]\npaths = [\"a\", \"b\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            loaded.get("work"),
+            Some(&["~/.ssh/config.work".to_string()][..])
+        );
+        assert_eq!(
+            loaded.get("personal"),
+            Some(&["a".to_string(), "b".to_string()][..])
+        );
+        assert_eq!(loaded.get("home"), None);
+        assert_eq!(loaded.names().collect::<Vec<_>>(), ["work", "personal"]);
+
+        assert_eq!(profiles("").unwrap(), Profiles::default());
+        assert!(profiles("[profiles.work]\n").is_err());
+        assert!(profiles("[profiles.work]\npaths = []\n").is_err());
+        assert!(profiles("[profiles.work]\npaths = \"a\"\n").is_err());
+        assert!(profiles("[profiles.work]\npaths = [1]\n").is_err());
+        assert!(profiles("[profiles.work]\npaths = [\"a\"]\nother = 1\n").is_err());
+    }
+}
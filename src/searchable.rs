@@ -1,46 +1,99 @@
-type SearchableFn<T> = dyn FnMut(&&T, &str) -> bool;
+use std::cmp::Reverse;
 
-pub struct Searchable<T>
-where
-    T: Clone,
-{
+/// Scores an item against a search, `None` if it doesn't match.
+type ScoreFn<T> = dyn FnMut(&&T, &str) -> Option<i64>;
+
+pub struct Searchable<T> {
     vec: Vec<T>,
 
-    filter: Box<SearchableFn<T>>,
-    filtered: Vec<T>,
+    score: Box<ScoreFn<T>>,
+    /// Indexes in `vec` of the items matching the search, the best scores first.
+    filtered: Vec<usize>,
+    /// Number of items matching the search, [`Searchable::truncate_matches`] not counted.
+    match_count: usize,
 }
 
-impl<T> Searchable<T>
-where
-    T: Clone,
-{
+impl<T> Searchable<T> {
+    /// Matching items keep their order.
     #[must_use]
-    pub fn new<P>(vec: Vec<T>, search_value: &str, predicate: P) -> Self
+    pub fn new<P>(vec: Vec<T>, search_value: &str, mut predicate: P) -> Self
     where
         P: FnMut(&&T, &str) -> bool + 'static,
+    {
+        Self::with_score(vec, search_value, move |item: &&T, value: &str| {
+            predicate(item, value).then_some(0)
+        })
+    }
+
+    /// Matching items are sorted by descending score, keeping their order on equal scores.
+    #[must_use]
+    pub fn with_score<S>(vec: Vec<T>, search_value: &str, score: S) -> Self
+    where
+        S: FnMut(&&T, &str) -> Option<i64> + 'static,
     {
         let mut searchable = Self {
             vec,
 
-            filter: Box::new(predicate),
+            score: Box::new(score),
             filtered: Vec::new(),
+            match_count: 0,
         };
         searchable.search(search_value);
         searchable
     }
 
     pub fn search(&mut self, value: &str) {
+        self.filtered.clear();
+
         if value.is_empty() {
-            self.filtered.clone_from(&self.vec);
+            self.filtered.extend(0..self.vec.len());
+            self.match_count = self.filtered.len();
             return;
         }
 
-        self.filtered = self
+        let score = &mut self.score;
+        let mut scored = self
             .vec
             .iter()
-            .filter(|host| (self.filter)(host, value))
-            .cloned()
-            .collect();
+            .enumerate()
+            .filter_map(|(index, item)| Some((index, score(&item, value)?)))
+            .collect::<Vec<_>>();
+        scored.sort_by_key(|&(_, score)| Reverse(score));
+
+        self.filtered
+            .extend(scored.into_iter().map(|(index, _)| index));
+        self.match_count = self.filtered.len();
+    }
+
+    /// Keeps the first `len` items matching the search until the next search.
+    pub fn truncate_matches(&mut self, len: usize) {
+        self.filtered.truncate(len);
+    }
+
+    /// Replaces the items, searching them again.
+    pub fn replace(&mut self, vec: Vec<T>, search_value: &str) {
+        self.vec = vec;
+        self.search(search_value);
+    }
+
+    /// Sorts the items matching the search by key, keeping their order on equal keys.
+    pub fn sort_matches_by_key<K, F>(&mut self, mut key: F)
+    where
+        K: Ord,
+        F: FnMut(&T) -> K,
+    {
+        let vec = &self.vec;
+        self.filtered.sort_by_key(|&index| key(&vec[index]));
+    }
+
+    /// Reverses the order of the items, the matches keep their order.
+    pub fn reverse(&mut self) {
+        self.vec.reverse();
+
+        let last_index = self.vec.len().saturating_sub(1);
+        for index in &mut self.filtered {
+            *index = last_index - *index;
+        }
     }
 
     #[allow(clippy::must_use_candidate)]
@@ -48,39 +101,110 @@ where
         self.filtered.len()
     }
 
+    /// Number of items matching the search, including those removed by
+    /// [`Searchable::truncate_matches`].
+    #[allow(clippy::must_use_candidate)]
+    pub fn match_count(&self) -> usize {
+        self.match_count
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub fn is_empty(&self) -> bool {
         self.filtered.is_empty()
     }
 
-    pub fn non_filtered_iter(&self) -> std::slice::Iter<T> {
+    pub fn non_filtered_iter(&self) -> std::slice::Iter<'_, T> {
         self.vec.iter()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<T> {
-        self.filtered.iter()
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: &self.vec,
+            indexes: self.filtered.iter(),
+        }
     }
 }
 
-impl<'a, T> IntoIterator for &'a Searchable<T>
-where
-    T: Clone,
-{
+/// Iterator over the items matching the search.
+pub struct Iter<'a, T> {
+    vec: &'a [T],
+    indexes: std::slice::Iter<'a, usize>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.indexes.next().map(|&index| &self.vec[index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indexes.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.indexes.next_back().map(|&index| &self.vec[index])
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Searchable<T> {
     type Item = &'a T;
-    type IntoIter = std::slice::Iter<'a, T>;
+    type IntoIter = Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.filtered.iter()
+        self.iter()
     }
 }
 
-impl<T> std::ops::Index<usize> for Searchable<T>
-where
-    T: Clone,
-{
+impl<T> std::ops::Index<usize> for Searchable<T> {
     type Output = T;
 
     fn index(&self, index: usize) -> &Self::Output {
-        &self.filtered[index]
+        &self.vec[self.filtered[index]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(searchable: &Searchable<&'static str>) -> Vec<&'static str> {
+        searchable.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_search_with_score() {
+        // Shorter words score higher
+        let mut searchable = Searchable::with_score(
+            vec!["b", "ab", "abc", "a", "c"],
+            "",
+            |word: &&&str, value: &str| {
+                word.contains(value)
+                    .then(|| -i64::try_from(word.len()).unwrap())
+            },
+        );
+
+        assert_eq!(words(&searchable), ["b", "ab", "abc", "a", "c"]);
+
+        searchable.search("a");
+        assert_eq!(words(&searchable), ["a", "ab", "abc"]);
+        assert_eq!(searchable[0], "a");
+
+        searchable.search("b");
+        assert_eq!(words(&searchable), ["b", "ab", "abc"]);
+
+        searchable.truncate_matches(2);
+        assert_eq!(words(&searchable), ["b", "ab"]);
+        assert_eq!(searchable.len(), 2);
+        assert_eq!(searchable.match_count(), 3);
+
+        searchable.search("");
+        assert_eq!(searchable.len(), 5);
+        assert_eq!(searchable.match_count(), 5);
     }
 }
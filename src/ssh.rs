@@ -1,11 +1,239 @@
 use anyhow::anyhow;
-use handlebars::Handlebars;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
+use handlebars::{Handlebars, PathSeg, RenderErrorReason, Template};
 use itertools::Itertools;
 use serde::Serialize;
-use std::collections::VecDeque;
-use std::process::Command;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::io::Read as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 use crate::ssh_config::{self, parser_error::ParseError, HostVecExt};
+use crate::timing::{measure, Stage};
+
+/// Where the rendered command gets spawned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Launcher {
+    /// Run the command in the current terminal
+    #[default]
+    None,
+    /// Open a new tmux window when running inside tmux
+    Tmux,
+    /// Open a new wezterm tab when running inside wezterm
+    Wezterm,
+    /// Open a new kitty tab when running inside kitty (requires `allow_remote_control`)
+    Kitty,
+}
+
+impl Launcher {
+    /// Wraps the command arguments so they are spawned by the launcher.
+    ///
+    /// Falls back to running the command as-is when sshs isn't running inside the launcher.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command cannot be quoted for the launcher.
+    pub fn wrap(self, name: &str, args: Vec<String>) -> anyhow::Result<Vec<String>> {
+        let is_inside = |variable: &str| std::env::var_os(variable).is_some();
+
+        let prefix = match self {
+            Launcher::Tmux if is_inside("TMUX") => {
+                let command = shlex::try_join(args.iter().map(String::as_str))?;
+                return Ok(vec![
+                    "tmux".to_string(),
+                    "new-window".to_string(),
+                    "-n".to_string(),
+                    name.to_string(),
+                    command,
+                ]);
+            }
+            Launcher::Wezterm if is_inside("WEZTERM_PANE") => vec!["wezterm", "cli", "spawn", "--"],
+            Launcher::Kitty if is_inside("KITTY_WINDOW_ID") => {
+                vec!["kitty", "@", "launch", "--type=tab", "--tab-title", name]
+            }
+            _ => return Ok(args),
+        };
+
+        Ok(prefix.into_iter().map(str::to_string).chain(args).collect())
+    }
+}
+
+/// Field of a [`Host`] that can be selected from the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HostField {
+    Name,
+    Aliases,
+    User,
+    Destination,
+    Port,
+    ProxyCommand,
+    IdentityAgent,
+    Source,
+    /// Label given by the labels file
+    Label,
+    /// Tags given by annotation comments and the `Tag` directive
+    Tags,
+    /// Comment of the `Host` line or first comment of the host block
+    Description,
+}
+
+impl HostField {
+    #[must_use]
+    pub fn title(self) -> &'static str {
+        match self {
+            HostField::Name => "Name",
+            HostField::Aliases => "Aliases",
+            HostField::User => "User",
+            HostField::Destination => "Destination",
+            HostField::Port => "Port",
+            HostField::ProxyCommand => "Proxy",
+            HostField::IdentityAgent => "Agent",
+            HostField::Source => "Source",
+            HostField::Label => "Label",
+            HostField::Tags => "Tags",
+            HostField::Description => "Description",
+        }
+    }
+
+    #[must_use]
+    pub fn value(self, host: &Host) -> Option<Cow<'_, str>> {
+        match self {
+            HostField::Name => Some(Cow::Borrowed(&host.name)),
+            HostField::Aliases => Some(Cow::Borrowed(&host.aliases)),
+            HostField::User => host.user.as_deref().map(Cow::Borrowed),
+            HostField::Destination => Some(Cow::Borrowed(&host.destination)),
+            HostField::Port => host.port.as_deref().map(Cow::Borrowed),
+            HostField::ProxyCommand => host.proxy_command.as_deref().map(Cow::Borrowed),
+            HostField::IdentityAgent => host.identity_agent.as_deref().map(Cow::Borrowed),
+            HostField::Source => host
+                .source_path
+                .as_deref()
+                .and_then(Path::to_str)
+                .map(Cow::Borrowed),
+            HostField::Label => host.label.as_deref().map(Cow::Borrowed),
+            HostField::Tags => (!host.tags.is_empty()).then(|| Cow::Owned(host.tags.join(" "))),
+            HostField::Description => host.description.as_deref().map(Cow::Borrowed),
+        }
+    }
+}
+
+/// Hop of the chain of hosts a connection goes through, see [`Host::proxy_chain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyHop {
+    /// Jump host as written in `ProxyJump`, with its destination when it is a configured host.
+    Jump {
+        name: String,
+        destination: Option<String>,
+    },
+    /// Command connecting to the previous hop.
+    Command(String),
+    /// Jump host that is already in the chain, ending it.
+    Cycle(String),
+}
+
+/// Host name of a `ProxyJump` destination, written `[ssh://][user@]host[:port]`.
+fn jump_host_name(jump: &str) -> &str {
+    let jump = jump.strip_prefix("ssh://").unwrap_or(jump);
+    let jump = jump.rsplit_once('@').map_or(jump, |(_, address)| address);
+
+    match jump.strip_prefix('[') {
+        Some(address) => address
+            .split_once(']')
+            .map_or(address, |(address, _)| address),
+        None => jump.split_once(':').map_or(jump, |(host, _)| host),
+    }
+}
+
+/// Standard input and outputs of the commands run by [`Host::run_command_template`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdioMode {
+    /// The terminal of sshs, the command is printed before it runs
+    Inherit,
+    /// None, for the commands running in the background while the terminal shows sshs
+    Null,
+}
+
+impl StdioMode {
+    fn stdio(self) -> Stdio {
+        match self {
+            StdioMode::Inherit => Stdio::inherit(),
+            StdioMode::Null => Stdio::null(),
+        }
+    }
+}
+
+/// Field used to order the hosts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortBy {
+    #[default]
+    Name,
+    User,
+    Destination,
+    Port,
+    /// Keep the configuration file order
+    None,
+}
+
+/// Direction of the [`SortBy`] order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortDir {
+    #[default]
+    Asc,
+    /// Reverse of the ascending order
+    Desc,
+}
+
+impl SortDir {
+    #[must_use]
+    pub fn reversed(self) -> SortDir {
+        match self {
+            SortDir::Asc => SortDir::Desc,
+            SortDir::Desc => SortDir::Asc,
+        }
+    }
+}
+
+impl SortBy {
+    /// Sorts the hosts by the field, then by name. Hosts without the field are pushed last,
+    /// or first in the descending order which is the exact reverse of the ascending one.
+    pub fn sort(self, hosts: &mut [Host], dir: SortDir) {
+        let by_name = |a: &Host, b: &Host| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let by_optional = |a: Option<&String>, b: Option<&String>| match (a, b) {
+            (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        let by_field = |a: &Host, b: &Host| match self {
+            SortBy::Name | SortBy::None => Ordering::Equal,
+            SortBy::User => by_optional(a.user.as_ref(), b.user.as_ref()),
+            SortBy::Destination => by_optional(
+                Some(&a.destination).filter(|destination| !destination.is_empty()),
+                Some(&b.destination).filter(|destination| !destination.is_empty()),
+            ),
+            SortBy::Port => {
+                let port = |host: &Host| host.port.as_ref().map(|port| port.parse::<u16>());
+                match (port(a), port(b)) {
+                    (Some(Ok(a)), Some(Ok(b))) => a.cmp(&b),
+                    _ => by_optional(a.port.as_ref(), b.port.as_ref()),
+                }
+            }
+        };
+
+        if self != SortBy::None {
+            hosts.sort_by(|a, b| by_field(a, b).then_with(|| by_name(a, b)));
+        }
+        if dir == SortDir::Desc {
+            hosts.reverse();
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Clone)]
 pub struct Host {
@@ -13,38 +241,666 @@ pub struct Host {
     pub aliases: String,
     pub user: Option<String>,
     pub destination: String,
+    /// `false` when the destination was derived from the host name because no `Hostname` applies to it.
+    #[serde(skip)]
+    pub has_explicit_hostname: bool,
     pub port: Option<String>,
+    /// `true` when the port isn't a valid port number, it is then kept as written in the configuration.
+    #[serde(skip)]
+    pub has_invalid_port: bool,
     pub proxy_command: Option<String>,
+    pub proxy_jump: Option<String>,
+    /// `IdentityAgent` of the host, see [`expand_identity_agent`].
+    pub identity_agent: Option<String>,
+    pub address_family: Option<String>,
+    pub bind_address: Option<String>,
+    /// `ConnectTimeout` of the host in seconds, replaced by `--connect-timeout` when given.
+    pub connect_timeout: Option<String>,
+    /// File the host is defined in.
+    pub source_path: Option<PathBuf>,
+    /// Short label given by the first matching entry of the labels file.
+    pub label: Option<String>,
+    /// Color of the host row given by the first matching entry of the labels file, as a color
+    /// name or a hexadecimal `#rrggbb` code like in the labels file.
+    #[serde(skip)]
+    pub label_color: Option<String>,
+    /// Tags given by `#sshs` annotations in the host block or the patterns applying to it,
+    /// then by its `Tag` directive.
+    pub tags: Vec<String>,
+    /// Command template given by a `# sshs-command:` annotation, replacing the command
+    /// template when connecting to the host, see [`Host::command_template`].
+    pub command_override: Option<String>,
+    /// Comment describing the host, see [`ssh_config::Host::get_description`].
+    pub description: Option<String>,
+    /// Directives set for the host, by itself or inherited, lowercased like `proxyjump`.
+    #[serde(skip)]
+    pub directives: BTreeSet<String>,
+    /// Resolved entries as `(key, value)` pairs sorted by key, see [`resolve_host_entries`].
+    #[serde(skip)]
+    pub entries: Vec<(String, String)>,
 }
 
 impl Host {
-    /// Uses the provided Handlebars template to run a command.
+    /// Builds a host that isn't in the configuration from a `[user@]host[:port]` destination,
+    /// IPv6 addresses being written in brackets when a port is given (e.g. `[::1]:2222`).
+    ///
+    /// The name is a destination ssh understands, `ssh://[user@]host[:port]` when a port is given.
+    #[must_use]
+    pub fn ad_hoc(destination: &str) -> Option<Host> {
+        let (user, address) = match destination.rsplit_once('@') {
+            Some((user, address)) => (Some(user), address),
+            None => (None, destination),
+        };
+        if user.is_some_and(|user| user.is_empty() || user.contains(char::is_whitespace)) {
+            return None;
+        }
+
+        let (hostname, port) = if let Some(address) = address.strip_prefix('[') {
+            let (hostname, rest) = address.split_once(']')?;
+            match rest {
+                "" => (hostname, None),
+                rest => (hostname, Some(rest.strip_prefix(':')?)),
+            }
+        } else if address.matches(':').count() > 1 {
+            // IPv6 address without port
+            (address, None)
+        } else {
+            match address.split_once(':') {
+                Some((hostname, port)) => (hostname, Some(port)),
+                None => (address, None),
+            }
+        };
+
+        let is_valid_hostname = !hostname.is_empty()
+            && hostname
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '%'));
+        if !is_valid_hostname {
+            return None;
+        }
+
+        let port = match port {
+            Some(port) => Some(
+                port.parse::<u16>()
+                    .ok()
+                    .filter(|port| *port != 0)?
+                    .to_string(),
+            ),
+            None => None,
+        };
+
+        let user_prefix = user.map(|user| format!("{user}@")).unwrap_or_default();
+        let name = match &port {
+            Some(port) if hostname.contains(':') => {
+                format!("ssh://{user_prefix}[{hostname}]:{port}")
+            }
+            Some(port) => format!("ssh://{user_prefix}{hostname}:{port}"),
+            None => format!("{user_prefix}{hostname}"),
+        };
+
+        Some(Host {
+            name,
+            aliases: String::new(),
+            user: user.map(str::to_string),
+            destination: hostname.to_string(),
+            has_explicit_hostname: true,
+            port,
+            has_invalid_port: false,
+            proxy_command: None,
+            proxy_jump: None,
+            identity_agent: None,
+            address_family: None,
+            bind_address: None,
+            connect_timeout: None,
+            source_path: None,
+            label: None,
+            label_color: None,
+            tags: Vec::new(),
+            command_override: None,
+            description: None,
+            directives: BTreeSet::new(),
+            entries: Vec::new(),
+        })
+    }
+
+    /// Returns `true` if `name` is the name or one of the aliases of the host.
+    #[must_use]
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.aliases.split(", ").any(|alias| alias == name)
+    }
+
+    /// Hops the connection to the host goes through, from the closest to the host to the
+    /// farthest one.
+    ///
+    /// Jump hosts defined in `hosts` are followed through their own `ProxyJump` or
+    /// `ProxyCommand`, the chain stops at the first jump host already in it.
+    #[must_use]
+    pub fn proxy_chain(&self, hosts: &[Host]) -> Vec<ProxyHop> {
+        let mut chain = Vec::new();
+        let mut visited = vec![self.name.as_str()];
+        let mut next_host = Some(self);
+
+        while let Some(host) = next_host.take() {
+            if let Some(jumps) = host
+                .proxy_jump
+                .as_deref()
+                .filter(|jumps| !jumps.eq_ignore_ascii_case("none"))
+            {
+                // The last jump host is the closest one, the first one is reached first
+                for jump in jumps.split(',').map(str::trim).rev() {
+                    let name = jump_host_name(jump);
+                    if visited.contains(&name) {
+                        chain.push(ProxyHop::Cycle(jump.to_string()));
+                        return chain;
+                    }
+                    visited.push(name);
+
+                    next_host = hosts.iter().find(|host| host.is_named(name));
+                    chain.push(ProxyHop::Jump {
+                        name: jump.to_string(),
+                        destination: next_host.map(|host| host.destination.clone()),
+                    });
+                }
+            } else if let Some(command) = host
+                .proxy_command
+                .as_deref()
+                .filter(|command| !command.eq_ignore_ascii_case("none"))
+            {
+                chain.push(ProxyHop::Command(command.to_string()));
+            }
+        }
+
+        chain
+    }
+
+    /// Returns `true` if the search is empty or fuzzy matches one of the fields.
+    #[must_use]
+    pub fn matches_search(
+        &self,
+        matcher: &SkimMatcherV2,
+        fields: &[HostField],
+        search: &str,
+    ) -> bool {
+        self.search_score(matcher, fields, search).is_some()
+    }
+
+    /// Best fuzzy match score of the search among the fields, `0` for an empty search and
+    /// `None` if no field matches.
+    ///
+    /// The `#tag` words of the search only keep the hosts with these tags, the
+    /// `has:<directive>` ones the hosts setting these directives, e.g. `has:identityfile`, and
+    /// the `^prefix` ones the hosts whose name starts with the prefix, whatever its case.
+    #[must_use]
+    pub fn search_score(
+        &self,
+        matcher: &SkimMatcherV2,
+        fields: &[HostField],
+        search: &str,
+    ) -> Option<i64> {
+        let (filters, words) = split_search(search);
+
+        let matches_filters = filters.iter().all(|filter| {
+            if let Some(prefix) = filter.strip_prefix(NAME_PREFIX_FILTER) {
+                return self.name.to_lowercase().starts_with(&prefix.to_lowercase());
+            }
+
+            match directive(filter) {
+                Some(directive) => self.directives.contains(&directive),
+                None => self
+                    .tags
+                    .iter()
+                    .any(|host_tag| host_tag.eq_ignore_ascii_case(&filter[1..])),
+            }
+        });
+        if !matches_filters {
+            return None;
+        }
+
+        if words.is_empty() {
+            return Some(0);
+        }
+
+        let search = if filters.is_empty() && words.len() == search.split_whitespace().count() {
+            Cow::Borrowed(search)
+        } else {
+            Cow::Owned(words.join(" "))
+        };
+
+        fields
+            .iter()
+            .filter_map(|field| matcher.fuzzy_match(&field.value(self)?, &search))
+            .max()
+    }
+
+    /// Alias fuzzy matching the search better than the name, the first one among the best, or
+    /// `None` if the name matches at least as well, see `--show-matched-alias`.
+    ///
+    /// The filter words of the search are left out, see [`Host::search_score`].
+    #[must_use]
+    pub fn matched_alias(&self, matcher: &SkimMatcherV2, search: &str) -> Option<&str> {
+        let (_, words) = split_search(search);
+        if words.is_empty() {
+            return None;
+        }
+
+        let search = words.join(" ");
+        let name_score = matcher.fuzzy_match(&self.name, &search);
+
+        // The last of the best ones is kept, so the aliases are walked backwards
+        self.aliases
+            .rsplit(", ")
+            .filter_map(|alias| Some((matcher.fuzzy_match(alias, &search)?, alias)))
+            .max_by_key(|(score, _)| *score)
+            .filter(|(score, _)| name_score.is_none_or(|name_score| *score > name_score))
+            .map(|(_, alias)| alias)
+    }
+
+    /// URL opened in the browser for hosts tagged [`WEB_TAG`], e.g. `https://dashboard.example.com:8443`.
+    #[must_use]
+    pub fn web_url(&self) -> Option<String> {
+        if !self.tags.iter().any(|tag| tag == WEB_TAG) {
+            return None;
+        }
+
+        Some(
+            match self.port.as_ref().filter(|_| !self.has_invalid_port) {
+                Some(port) => format!("https://{}:{port}", self.bracketed_destination()),
+                None => format!("https://{}", self.bracketed_destination()),
+            },
+        )
+    }
+
+    /// Destination followed by the port when there is one, IPv6 addresses being written in
+    /// brackets then, e.g. `[2001:db8::1]:2222`.
+    #[must_use]
+    pub fn address(&self) -> String {
+        match &self.port {
+            Some(port) => format!("{}:{port}", self.bracketed_destination()),
+            None => self.destination.clone(),
+        }
+    }
+
+    /// Destination with IPv6 addresses in brackets, host names can't contain a colon.
+    fn bracketed_destination(&self) -> Cow<'_, str> {
+        if self.destination.contains(':') {
+            Cow::Owned(format!("[{}]", self.destination))
+        } else {
+            Cow::Borrowed(&self.destination)
+        }
+    }
+
+    /// Template of the command connecting to the host: its `# sshs-command:` annotation if it
+    /// has one, `template` otherwise.
+    #[must_use]
+    pub fn command_template<'a>(&'a self, template: &'a str) -> &'a str {
+        self.command_override.as_deref().unwrap_or(template)
+    }
+
+    /// Uses the provided Handlebars template to run a command and returns its exit status.
     ///
     /// # Errors
     ///
     /// Will return `Err` if the command cannot be executed.
+    pub fn run_command_template(
+        &self,
+        pattern: &str,
+        launcher: Launcher,
+        extra_args: &[String],
+        stdio: StdioMode,
+    ) -> anyhow::Result<ExitStatus> {
+        let (rendered_command, args) =
+            self.render_command_template(pattern, launcher, extra_args)?;
+
+        if stdio == StdioMode::Inherit {
+            println!("Running command: {rendered_command}");
+        }
+
+        let mut args = args.into_iter().collect::<VecDeque<String>>();
+        let command = args.pop_front().ok_or(anyhow!("Failed to get command"))?;
+
+        let status = Command::new(command)
+            .args(args)
+            .stdin(stdio.stdio())
+            .stdout(stdio.stdio())
+            .stderr(stdio.stdio())
+            .spawn()?
+            .wait()?;
+
+        Ok(status)
+    }
+
+    /// Renders the provided Handlebars template and splits it into the argv that would be spawned
+    /// by the launcher.
+    ///
+    /// The template gets the host fields along with the `address` and the values of ssh's `%d`
+    /// (`home`), `%r` (`remote_user`) and `%u` (`local_user`) tokens, see [`TemplateContext`].
     ///
-    /// # Panics
+    /// The `extra_args` follow the program of a template running `ssh`, before the destination
+    /// and any remote command, inside the command the launcher spawns, see `--ssh-args`. They
+    /// aren't given to other programs.
     ///
-    /// Will panic if the regex cannot be compiled.
-    pub fn run_command_template(&self, pattern: &str) -> anyhow::Result<()> {
-        let handlebars = Handlebars::new();
-        let rendered_command = handlebars.render_template(pattern, &self)?;
+    /// # Errors
+    ///
+    /// Will return `Err` if the template cannot be rendered or the command cannot be parsed.
+    pub fn render_command_template(
+        &self,
+        pattern: &str,
+        launcher: Launcher,
+        extra_args: &[String],
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        let rendered_command = TemplateContext::new(self).render(pattern)?;
+
+        let mut args = split_command(&rendered_command)
+            .ok_or(anyhow!("Failed to parse command: {rendered_command}"))?;
+        if args.first().is_some_and(|program| is_ssh_program(program)) {
+            args.splice(1..1, extra_args.iter().cloned());
+        }
+        let args = launcher.wrap(&self.name, args)?;
+
+        Ok((rendered_command, args))
+    }
+}
+
+/// Splits the search into its filter words, `#tag`, `has:<directive>` and `^prefix`, and the
+/// words fuzzy matched against the host fields.
+///
+/// A bare `#`, `has:` or `^`, a filter being typed, is left out of both.
+fn split_search(search: &str) -> (Vec<&str>, Vec<&str>) {
+    search
+        .split_whitespace()
+        .filter(|word| {
+            word.strip_prefix(['#', NAME_PREFIX_FILTER]) != Some("")
+                && !word.eq_ignore_ascii_case(HAS_FILTER_PREFIX)
+        })
+        .partition(|word| word.starts_with(['#', NAME_PREFIX_FILTER]) || directive(word).is_some())
+}
+
+/// Prefix of the search filters keeping the hosts whose name starts with the rest of the word,
+/// e.g. `^web`.
+pub const NAME_PREFIX_FILTER: char = '^';
+
+/// Prefix of the search filters keeping the hosts setting a directive, e.g. `has:proxyjump`.
+pub const HAS_FILTER_PREFIX: &str = "has:";
+
+/// Directive of a `has:<directive>` search filter, lowercased, `None` if the word isn't one.
+fn directive(word: &str) -> Option<String> {
+    let (prefix, directive) = word.split_at_checked(HAS_FILTER_PREFIX.len())?;
+    if !prefix.eq_ignore_ascii_case(HAS_FILTER_PREFIX) {
+        return None;
+    }
+
+    ssh_config::EntryType::from_str(directive)
+        .ok()
+        .map(|directive| directive.to_string().to_lowercase())
+}
 
-        println!("Running command: {rendered_command}");
+/// Template of the command run on enter when `--template` isn't given.
+///
+/// The name is double quoted so names with spaces stay one argument, which both the POSIX and
+/// the Windows rules of [`split_command`] understand.
+pub const DEFAULT_COMMAND_TEMPLATE: &str = "ssh \"{{{name}}}\"";
+
+/// Host fields matched by the search when `--search-fields` isn't given.
+pub const DEFAULT_SEARCH_FIELDS: [HostField; 4] = [
+    HostField::Name,
+    HostField::Destination,
+    HostField::Aliases,
+    HostField::Tags,
+];
+
+/// Splits a rendered command into its program and arguments, like the shell of the platform:
+/// POSIX shell words, or the Windows command line rules where backslashes are path separators.
+///
+/// Returns `None` if the command cannot be split, e.g. on an unterminated quote.
+#[must_use]
+pub fn split_command(command: &str) -> Option<Vec<String>> {
+    if cfg!(windows) {
+        Some(split_windows_command(command))
+    } else {
+        shlex::split(command)
+    }
+}
+
+/// Splits a command line like `CommandLineToArgvW`: arguments are separated by spaces or tabs
+/// outside of double quotes, and backslashes only escape the double quotes they precede.
+fn split_windows_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut is_quoted = false;
+    let mut chars = command.chars().peekable();
 
-        let mut args = shlex::split(&rendered_command)
-            .ok_or(anyhow!("Failed to parse command: {rendered_command}"))?
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' if !is_quoted => {
+                args.extend(arg.take());
+            }
+            '\\' => {
+                let mut backslashes = 1;
+                while chars.next_if_eq(&'\\').is_some() {
+                    backslashes += 1;
+                }
+
+                let arg = arg.get_or_insert_with(String::new);
+                if chars.peek() == Some(&'"') {
+                    // Halved before a double quote, an odd one escaping it
+                    arg.extend(std::iter::repeat_n('\\', backslashes / 2));
+                    if backslashes % 2 == 1 {
+                        arg.push('"');
+                        chars.next();
+                    }
+                } else {
+                    arg.extend(std::iter::repeat_n('\\', backslashes));
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                // Two double quotes inside quotes are a literal one
+                if is_quoted && chars.next_if_eq(&'"').is_some() {
+                    arg.push('"');
+                } else {
+                    is_quoted = !is_quoted;
+                }
+            }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+
+    args
+}
+
+/// Returns `true` if the program of a command is `ssh`, found in the `PATH` or not.
+fn is_ssh_program(program: &str) -> bool {
+    program == "ssh" || program.ends_with("/ssh")
+}
+
+/// Adds `-o ConnectTimeout={{connect_timeout}}` after the program of a template running `ssh`,
+/// see `--connect-timeout`. Templates running another program, already giving a `ConnectTimeout`
+/// option or using the `{{connect_timeout}}` variable, are returned as they are.
+#[must_use]
+pub fn with_connect_timeout(template: &str) -> String {
+    let command = template.trim_start();
+    let program_len = command.find(char::is_whitespace).unwrap_or(command.len());
+    let program = &command[..program_len];
+
+    let uses_timeout = TemplateContext::referenced_variables(template).is_ok_and(|variables| {
+        variables
+            .iter()
+            .any(|variable| variable == "connect_timeout")
+    }) || split_command(template)
+        .is_some_and(|args| sets_connect_timeout(&args));
+    if !is_ssh_program(program) || uses_timeout {
+        return template.to_string();
+    }
+
+    let indent = template.len() - command.len();
+    format!(
+        "{} -o ConnectTimeout={{{{connect_timeout}}}}{}",
+        &template[..indent + program_len],
+        &command[program_len..]
+    )
+}
+
+/// Returns `true` if the arguments give ssh a `ConnectTimeout` option, e.g.
+/// `-o ConnectTimeout=5` or `-oConnectTimeout=5`.
+fn sets_connect_timeout(args: &[String]) -> bool {
+    const OPTION: &str = "ConnectTimeout";
+    let is_timeout_option = |option: &str| {
+        option
+            .split_at_checked(OPTION.len())
+            .is_some_and(|(name, rest)| {
+                name.eq_ignore_ascii_case(OPTION) && rest.starts_with(['=', ' ', '\t'])
+            })
+    };
+
+    args.iter()
+        .enumerate()
+        .any(|(index, arg)| match arg.strip_prefix("-o") {
+            Some("") => args
+                .get(index + 1)
+                .is_some_and(|option| is_timeout_option(option)),
+            Some(option) => is_timeout_option(option),
+            None => false,
+        })
+}
+
+/// Values given to the command templates.
+#[derive(Debug, Serialize)]
+pub struct TemplateContext<'a> {
+    #[serde(flatten)]
+    pub host: &'a Host,
+    /// Destination with the port, see [`Host::address`].
+    pub address: String,
+    /// Local home directory, like ssh's `%d`.
+    pub home: String,
+    /// User logged in as on the host, like ssh's `%r`: the host `User`, else the local user.
+    pub remote_user: Option<String>,
+    /// Local user name, like ssh's `%u`.
+    pub local_user: Option<String>,
+}
+
+impl TemplateContext<'_> {
+    #[must_use]
+    pub fn new(host: &Host) -> TemplateContext<'_> {
+        let local_user = ["USER", "USERNAME"]
             .into_iter()
-            .collect::<VecDeque<String>>();
-        let command = args.pop_front().ok_or(anyhow!("Failed to get command"))?;
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|user| !user.is_empty());
 
-        let status = Command::new(command).args(args).spawn()?.wait()?;
-        if !status.success() {
-            std::process::exit(status.code().unwrap_or(1));
+        TemplateContext {
+            host,
+            address: host.address(),
+            home: shellexpand::tilde("~").into_owned(),
+            remote_user: host.user.clone().or_else(|| local_user.clone()),
+            local_user,
         }
+    }
 
-        Ok(())
+    /// Renders the template in strict mode: unknown variables are errors, unset ones are empty.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the template is invalid or uses an unknown variable, the error then
+    /// lists the variables.
+    pub fn render(&self, template: &str) -> anyhow::Result<String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+
+        handlebars
+            .render_template(template, self)
+            .map_err(|err| match err.reason() {
+                RenderErrorReason::MissingVariable(Some(name)) => anyhow!(
+                    "Unknown variable `{name}` in `{template}`, the variables are {}",
+                    TemplateContext::variables().join(", ")
+                ),
+                _ => anyhow!(err),
+            })
+    }
+
+    /// Names of the variables given to the templates.
+    #[must_use]
+    pub fn variables() -> Vec<String> {
+        let host = Host::ad_hoc("example.com").unwrap_or_else(|| unreachable!());
+        match serde_json::to_value(TemplateContext::new(&host)) {
+            Ok(serde_json::Value::Object(variables)) => variables.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Checks the template once before rendering it for the hosts, e.g. at startup.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the template cannot be rendered, see [`TemplateContext::render`].
+    pub fn validate(template: &str) -> anyhow::Result<()> {
+        let host = Host::ad_hoc("example.com").unwrap_or_else(|| unreachable!());
+        TemplateContext::new(&host).render(template).map(|_| ())
+    }
+
+    /// Names of the variables the template refers to, known or not, in the order they first
+    /// appear, see `--check-template`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the template cannot be parsed.
+    pub fn referenced_variables(template: &str) -> anyhow::Result<Vec<String>> {
+        let template = Template::compile(template)?;
+
+        let mut variables = Vec::new();
+        collect_template_variables(&template, &mut variables);
+        Ok(variables)
+    }
+}
+
+fn collect_template_variables(template: &Template, variables: &mut Vec<String>) {
+    for element in &template.elements {
+        collect_element_variables(element, variables);
+    }
+}
+
+fn collect_element_variables(element: &TemplateElement, variables: &mut Vec<String>) {
+    match element {
+        TemplateElement::Expression(helper)
+        | TemplateElement::HtmlExpression(helper)
+        | TemplateElement::HelperBlock(helper) => collect_helper_variables(helper, variables),
+        _ => {}
+    }
+}
+
+/// Collects the parameters of a helper, or the helper itself when it has none as `{{name}}`
+/// is parsed like a helper named `name`.
+fn collect_helper_variables(helper: &HelperTemplate, variables: &mut Vec<String>) {
+    let is_variable = !helper.block && helper.params.is_empty() && helper.hash.is_empty();
+    match &helper.name {
+        Parameter::Name(name) if is_variable => push_variable(name, variables),
+        name => collect_parameter_variables(name, variables),
+    }
+
+    for parameter in helper.params.iter().chain(helper.hash.values()) {
+        collect_parameter_variables(parameter, variables);
+    }
+    for template in helper.template.iter().chain(&helper.inverse) {
+        collect_template_variables(template, variables);
+    }
+}
+
+fn collect_parameter_variables(parameter: &Parameter, variables: &mut Vec<String>) {
+    match parameter {
+        Parameter::Path(handlebars::Path::Relative((segments, _))) => {
+            if let Some(PathSeg::Named(name)) = segments.first() {
+                push_variable(name, variables);
+            }
+        }
+        Parameter::Subexpression(subexpression) => {
+            collect_element_variables(&subexpression.element, variables);
+        }
+        _ => {}
+    }
+}
+
+fn push_variable(name: &str, variables: &mut Vec<String>) {
+    if name != "this" && !variables.iter().any(|variable| variable == name) {
+        variables.push(name.to_string());
     }
 }
 
@@ -54,6 +910,17 @@ pub enum ParseConfigError {
     SshConfig(ParseError),
 }
 
+impl std::fmt::Display for ParseConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseConfigError::Io(e) => write!(f, "{e}"),
+            ParseConfigError::SshConfig(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseConfigError {}
+
 impl From<std::io::Error> for ParseConfigError {
     fn from(e: std::io::Error) -> Self {
         ParseConfigError::Io(e)
@@ -66,34 +933,1153 @@ impl From<ParseError> for ParseConfigError {
     }
 }
 
+impl ParseConfigError {
+    /// Returns `true` if the SSH configuration file doesn't exist.
+    #[must_use]
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ParseConfigError::Io(e) if e.kind() == std::io::ErrorKind::NotFound)
+    }
+}
+
+/// Tag of the hosts that can be opened in the browser, e.g. `#sshs web`.
+pub const WEB_TAG: &str = "web";
+
+/// System-wide SSH configuration file, ignored when missing.
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/ssh/ssh_config";
+
+pub const USER_CONFIG_PATH: &str = "~/.ssh/config";
+
+/// Directory of the system-wide SSH configuration fragments, `None` where there is none.
+pub const SYSTEM_INCLUDE_DIR: Option<&str> = if cfg!(windows) {
+    None
+} else {
+    Some("/etc/ssh/ssh_config.d")
+};
+
+/// Path reading the SSH configuration from stdin.
+pub const STDIN_CONFIG_PATH: &str = "-";
+
+/// Configuration read from stdin, kept so reloading the hosts parses it again.
+static STDIN_CONFIG: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Reads the configuration from stdin the first time, then returns the same one.
+fn stdin_config() -> std::io::Result<&'static [u8]> {
+    if let Some(config) = STDIN_CONFIG.get() {
+        return Ok(config);
+    }
+
+    let mut config = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut config)?;
+    Ok(STDIN_CONFIG.get_or_init(|| config))
+}
+
+/// Parses the SSH configuration file, [`STDIN_CONFIG_PATH`] reading it from stdin.
+///
 /// # Errors
 ///
 /// Will return `Err` if the SSH configuration file cannot be parsed.
-pub fn parse_config(raw_path: &String) -> Result<Vec<Host>, ParseConfigError> {
-    let normalized_path = shellexpand::tilde(&raw_path).to_string();
-    let path = std::fs::canonicalize(normalized_path)?;
-
-    let hosts = ssh_config::Parser::new()
-        .parse_file(path)?
-        .apply_patterns()
-        .apply_name_to_empty_hostname()
-        .merge_same_hosts()
+pub fn parse_config(
+    raw_path: &str,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<Host>, ParseConfigError> {
+    Ok(resolve_hosts(
+        &parse_raw_config_entries(raw_path, parser)?,
+        parser.get_merge_same_hosts(),
+    ))
+}
+
+/// Parses the SSH configuration file at `raw_path` into hosts holding all their resolved entries,
+/// see [`parse_config_entries`].
+///
+/// `~` is expanded in the path and [`STDIN_CONFIG_PATH`] reads the configuration from stdin,
+/// only once for the whole run. Relative includes of a configuration read from stdin resolve
+/// from `~/.ssh`.
+fn parse_raw_config_entries(
+    raw_path: &str,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<ssh_config::Host>, ParseConfigError> {
+    if raw_path == STDIN_CONFIG_PATH {
+        return parse_reader_entries(&mut stdin_config()?, parser);
+    }
+
+    let path = shellexpand::tilde(raw_path).to_string();
+    parse_config_entries(path, parser)
+}
+
+/// Parses the SSH configuration read from `reader`, relative includes resolve from `~/.ssh`.
+///
+/// # Errors
+///
+/// Will return `Err` if the SSH configuration cannot be read or parsed.
+pub fn parse_config_reader(
+    reader: &mut impl std::io::BufRead,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<Host>, ParseConfigError> {
+    Ok(resolve_hosts(
+        &parse_reader_entries(reader, parser)?,
+        parser.get_merge_same_hosts(),
+    ))
+}
+
+fn parse_reader_entries(
+    reader: &mut impl std::io::BufRead,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<ssh_config::Host>, ParseConfigError> {
+    let hosts = measure(Stage::Parsing, || parser.parse(reader))?;
+    Ok(measure(Stage::ApplyingPatterns, || hosts.apply_patterns()))
+}
+
+/// Expands `~` in the path and makes it absolute.
+#[must_use]
+pub fn expand_path(raw_path: &str) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(raw_path).as_ref());
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// Parses the files of the directory at `raw_dir` like an `Include <dir>/*` line would,
+/// e.g. the fragments of `~/.ssh/config.d`.
+///
+/// # Errors
+///
+/// Will return `Err` if one of the files cannot be parsed.
+pub fn parse_include_dir(
+    raw_dir: &str,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<Host>, ParseConfigError> {
+    Ok(resolve_hosts(
+        &parse_include_dir_entries(raw_dir, parser)?,
+        parser.get_merge_same_hosts(),
+    ))
+}
+
+fn parse_include_dir_entries(
+    raw_dir: &str,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<ssh_config::Host>, ParseConfigError> {
+    let dir = glob::Pattern::escape(&expand_path(raw_dir).to_string_lossy());
+    let include = format!("Include {dir}/*\n");
+
+    let hosts = measure(Stage::Parsing, || parser.parse(&mut include.as_bytes()))?;
+    Ok(measure(Stage::ApplyingPatterns, || hosts.apply_patterns()))
+}
+
+/// Parses the raw entries of the SSH configuration files then of the include directories,
+/// skipping the system-wide configuration file when missing.
+fn parse_raw_configs_entries<'a>(
+    config_paths: &'a [String],
+    include_dirs: &'a [String],
+    parser: &'a ssh_config::Parser,
+) -> impl Iterator<Item = Result<Vec<ssh_config::Host>, ParseConfigError>> + 'a {
+    config_paths
         .iter()
-        .map(|host| Host {
-            name: host
+        .filter_map(move |path| match parse_raw_config_entries(path, parser) {
+            Err(err) if path == SYSTEM_CONFIG_PATH && err.is_not_found() => None,
+            result => Some(result),
+        })
+        .chain(
+            include_dirs
+                .iter()
+                .map(move |dir| parse_include_dir_entries(dir, parser)),
+        )
+}
+
+/// Parses the SSH configuration files one after the other, then the files of the include
+/// directories, skipping the system-wide configuration file when missing.
+pub fn parse_configs<'a>(
+    config_paths: &'a [String],
+    include_dirs: &'a [String],
+    parser: &'a ssh_config::Parser,
+) -> impl Iterator<Item = Result<Vec<Host>, ParseConfigError>> + 'a {
+    parse_raw_configs_entries(config_paths, include_dirs, parser)
+        .map(move |hosts| Ok(resolve_hosts(&hosts?, parser.get_merge_same_hosts())))
+}
+
+/// Parses the SSH configuration files like [`parse_configs`] into the entries of each host,
+/// with the hosts of all the files one after the other, e.g. to write them in a single file.
+///
+/// # Errors
+///
+/// Will return `Err` if one of the SSH configuration files cannot be parsed.
+pub fn parse_configs_entries(
+    config_paths: &[String],
+    include_dirs: &[String],
+    parser: &ssh_config::Parser,
+) -> Result<Vec<ssh_config::Host>, ParseConfigError> {
+    let mut hosts = Vec::new();
+    for parsed_hosts in parse_raw_configs_entries(config_paths, include_dirs, parser) {
+        hosts.extend(parsed_hosts?);
+    }
+
+    if parser.get_merge_same_hosts() {
+        hosts = measure(Stage::Merging, || hosts.merge_same_hosts());
+    }
+
+    Ok(hosts)
+}
+
+/// Same as [`parse_config`] but without expanding `~` in the path.
+///
+/// # Errors
+///
+/// Will return `Err` if the SSH configuration file cannot be parsed.
+pub fn parse_config_file<P>(
+    path: P,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<Host>, ParseConfigError>
+where
+    P: AsRef<Path>,
+{
+    Ok(resolve_hosts(
+        &parse_config_entries(path, parser)?,
+        parser.get_merge_same_hosts(),
+    ))
+}
+
+/// Tags of the annotation comments then the `Tag` directive of OpenSSH 9.4, unless the
+/// annotations have it too.
+fn host_tags(host: &ssh_config::Host) -> Vec<String> {
+    let mut tags = host.get_tags().to_vec();
+    if let Some(tag) = host
+        .get(&ssh_config::EntryType::Tag)
+        .filter(|tag| !tag.is_empty() && !tags.contains(tag))
+    {
+        tags.push(tag);
+    }
+    tags
+}
+
+/// Resolves the fields displayed by sshs from the hosts entries.
+fn resolve_hosts(hosts: &[ssh_config::Host], merge_same_hosts: bool) -> Vec<Host> {
+    // Names of the hosts that will get their `Hostname` from their name
+    let derived_hostnames = hosts
+        .iter()
+        .filter(|host| host.get(&ssh_config::EntryType::Hostname).is_none())
+        .filter_map(|host| host.get_patterns().first().cloned())
+        .collect::<HashSet<_>>();
+
+    let mut hosts = hosts.to_vec().apply_name_to_empty_hostname();
+    if merge_same_hosts {
+        let count = hosts.len();
+        hosts = measure(Stage::Merging, || hosts.merge_same_hosts());
+        log::debug!("Merged {count} hosts into {}", hosts.len());
+    }
+
+    hosts
+        .iter()
+        .map(|host| {
+            let name = host
                 .get_patterns()
                 .first()
                 .unwrap_or(&String::new())
-                .clone(),
-            aliases: host.get_patterns().iter().skip(1).join(", "),
-            user: host.get(&ssh_config::EntryType::User),
-            destination: host
-                .get(&ssh_config::EntryType::Hostname)
-                .unwrap_or_default(),
-            port: host.get(&ssh_config::EntryType::Port),
-            proxy_command: host.get(&ssh_config::EntryType::ProxyCommand),
+                .clone();
+            let has_explicit_hostname = !derived_hostnames.contains(&name)
+                || host.get(&ssh_config::EntryType::Hostname).as_ref() != Some(&name);
+            let destination = expand_hostname_tokens(
+                &host
+                    .get(&ssh_config::EntryType::Hostname)
+                    .unwrap_or_default(),
+                &name,
+            );
+
+            let port = host
+                .get(&ssh_config::EntryType::Port)
+                .map(|port| normalize_port(&port));
+
+            Host {
+                aliases: host.get_patterns().iter().skip(1).join(", "),
+                user: host.get(&ssh_config::EntryType::User),
+                destination,
+                has_explicit_hostname,
+                has_invalid_port: port.as_ref().is_some_and(|(_, is_valid)| !is_valid),
+                port: port.map(|(port, _)| port),
+                proxy_command: host.get(&ssh_config::EntryType::ProxyCommand),
+                proxy_jump: host.get(&ssh_config::EntryType::ProxyJump),
+                identity_agent: host
+                    .get(&ssh_config::EntryType::IdentityAgent)
+                    .map(|agent| expand_identity_agent(&agent)),
+                address_family: host.get(&ssh_config::EntryType::AddressFamily),
+                connect_timeout: host.get(&ssh_config::EntryType::ConnectTimeout),
+                bind_address: host.get(&ssh_config::EntryType::BindAddress),
+                source_path: host.get_source_path().map(Path::to_path_buf),
+                label: None,
+                label_color: None,
+                tags: host_tags(host),
+                command_override: host.get_command().map(str::to_string),
+                description: host.get_description().map(str::to_string),
+                directives: host
+                    .get_entries()
+                    .keys()
+                    // The hostname derived from the name isn't set in the configuration
+                    .filter(|entry| {
+                        has_explicit_hostname || **entry != ssh_config::EntryType::Hostname
+                    })
+                    .map(|entry| entry.to_string().to_lowercase())
+                    .collect(),
+                entries: resolved_entries(host, &name),
+                name,
+            }
         })
-        .collect();
+        .collect()
+}
+
+/// Parses the SSH configuration file into hosts holding all their resolved entries.
+///
+/// Includes, global options and patterns are applied, pattern hosts are removed and
+/// each host has a single pattern.
+///
+/// # Errors
+///
+/// Will return `Err` if the SSH configuration file cannot be parsed.
+pub fn parse_config_entries<P>(
+    path: P,
+    parser: &ssh_config::Parser,
+) -> Result<Vec<ssh_config::Host>, ParseConfigError>
+where
+    P: AsRef<Path>,
+{
+    let path = std::fs::canonicalize(path)?;
+    log::debug!("Parsing `{}`", path.display());
+
+    let hosts = measure(Stage::Parsing, || parser.parse_file(&path))?;
+    let hosts = measure(Stage::ApplyingPatterns, || hosts.apply_patterns());
+    log::debug!("`{}` defines {} hosts", path.display(), hosts.len());
 
     Ok(hosts)
 }
+
+/// An entry resolved by [`resolve_host_entries`] as `(key, value, is_inherited)`,
+/// `is_inherited` being `true` for the entries coming from the global options or from
+/// a pattern host rather than from the host block.
+pub type ResolvedEntry = (String, String, bool);
+
+/// Resolves the entries of the host named `name` like `ssh -G` does, sorted by key.
+///
+/// A name that isn't a host of its own (e.g. `foo.example.com` with only `Host *.example.com`)
+/// gets the entries of the patterns it matches and of the global options.
+/// Returns `None` if no entry applies to the name.
+///
+/// # Errors
+///
+/// Will return `Err` if one of the SSH configuration files cannot be parsed.
+pub fn resolve_host_entries(
+    config_paths: &[String],
+    include_dirs: &[String],
+    parser: &ssh_config::Parser,
+    name: &str,
+) -> Result<Option<Vec<ResolvedEntry>>, ParseConfigError> {
+    for hosts in parse_raw_configs_entries(config_paths, include_dirs, parser) {
+        let hosts = hosts?;
+
+        let Some(host) = hosts
+            .apply_name_to_empty_hostname()
+            .into_iter()
+            .find(|host| {
+                host.get_patterns()
+                    .first()
+                    .is_some_and(|pattern| pattern == name)
+            })
+        else {
+            continue;
+        };
+
+        return Ok(Some(resolved_entries_provenance(&host, name)));
+    }
+
+    for config in ad_hoc_host_configs(config_paths, include_dirs, name) {
+        let hosts = parser.parse(&mut config?.as_slice())?.apply_patterns();
+        let Some(host) = hosts
+            .into_iter()
+            .filter(|host| {
+                !host.is_empty()
+                    && host
+                        .get_patterns()
+                        .first()
+                        .is_some_and(|pattern| pattern == name)
+            })
+            .collect::<Vec<_>>()
+            .apply_name_to_empty_hostname()
+            .pop()
+        else {
+            continue;
+        };
+
+        return Ok(Some(resolved_entries_provenance(&host, name)));
+    }
+
+    Ok(None)
+}
+
+/// Configurations including each SSH configuration file or include directory,
+/// followed by a `Host <name>` block for the patterns and global options to apply to.
+fn ad_hoc_host_configs<'a>(
+    config_paths: &'a [String],
+    include_dirs: &'a [String],
+    name: &'a str,
+) -> impl Iterator<Item = Result<Vec<u8>, ParseConfigError>> + 'a {
+    let host_block = format!("\nHost {name}\n");
+
+    config_paths
+        .iter()
+        .filter_map(|raw_path| {
+            if raw_path == STDIN_CONFIG_PATH {
+                return Some(stdin_config().map(<[u8]>::to_vec).map_err(Into::into));
+            }
+
+            match std::fs::canonicalize(shellexpand::tilde(raw_path).as_ref()) {
+                Ok(path) => Some(Ok(format!(
+                    "Include {}",
+                    glob::Pattern::escape(&path.to_string_lossy())
+                )
+                .into_bytes())),
+                Err(err)
+                    if raw_path == SYSTEM_CONFIG_PATH
+                        && err.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    None
+                }
+                Err(err) => Some(Err(err.into())),
+            }
+        })
+        .chain(include_dirs.iter().map(|dir| {
+            let dir = glob::Pattern::escape(&expand_path(dir).to_string_lossy());
+            Ok(format!("Include {dir}/*").into_bytes())
+        }))
+        .map(move |config| {
+            config.map(|mut config| {
+                config.extend_from_slice(host_block.as_bytes());
+                config
+            })
+        })
+}
+
+/// Entries of the host named `name` like `ssh -G` prints them, sorted by key.
+fn resolved_entries(host: &ssh_config::Host, name: &str) -> Vec<(String, String)> {
+    host.get_entries()
+        .iter()
+        .map(|(entry_type, value)| resolved_entry(entry_type, value, name))
+        .sorted()
+        .collect()
+}
+
+/// Entries of the host like [`resolved_entries`], with whether they are inherited.
+fn resolved_entries_provenance(host: &ssh_config::Host, name: &str) -> Vec<ResolvedEntry> {
+    host.get_entries()
+        .iter()
+        .map(|(entry_type, value)| {
+            let (key, value) = resolved_entry(entry_type, value, name);
+            (key, value, host.is_inherited(entry_type))
+        })
+        .sorted()
+        .collect()
+}
+
+fn resolved_entry(entry_type: &ssh_config::EntryType, value: &str, name: &str) -> (String, String) {
+    let key = match entry_type {
+        ssh_config::EntryType::Unknown(key) => key.to_lowercase(),
+        entry_type => entry_type.to_string().to_lowercase(),
+    };
+    let value = match entry_type {
+        ssh_config::EntryType::Hostname => expand_hostname_tokens(value, name),
+        _ => value.to_string(),
+    };
+
+    (key, value)
+}
+
+/// Trims and unquotes the port, returning it with whether it is a valid port number.
+///
+/// Valid ports are normalized (e.g. `0022` becomes `22`), invalid ones are kept as written.
+fn normalize_port(raw_port: &str) -> (String, bool) {
+    let port = raw_port.trim();
+    let port = port
+        .strip_prefix('"')
+        .and_then(|port| port.strip_suffix('"'))
+        .unwrap_or(port)
+        .trim();
+
+    match port.parse::<u16>() {
+        Ok(number) if number != 0 => (number.to_string(), true),
+        _ => (port.to_string(), false),
+    }
+}
+
+/// Expands `~` and the environment variables in an `IdentityAgent` socket path like ssh does,
+/// `SSH_AUTH_SOCK` being replaced by the socket of this variable.
+///
+/// `none` and the paths with unset variables are kept as written.
+#[must_use]
+pub fn expand_identity_agent(agent: &str) -> String {
+    let agent = agent.trim_matches('"');
+    if agent == "SSH_AUTH_SOCK" {
+        return std::env::var(agent).unwrap_or_else(|_| agent.to_string());
+    }
+
+    shellexpand::full(agent).map_or_else(|_| agent.to_string(), Cow::into_owned)
+}
+
+/// Expands the `Hostname` tokens that can be resolved without connecting.
+///
+/// `%h` and `%n` are replaced by the host name and `%%` by a literal `%`.
+/// Other tokens (`%p`, `%r`, ...) are resolved by ssh at connect time and are kept as-is.
+fn expand_hostname_tokens(hostname: &str, name: &str) -> String {
+    let mut expanded = String::with_capacity(hostname.len());
+    let mut chars = hostname.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('h' | 'n') => expanded.push_str(name),
+            Some(token) if token != '%' => {
+                expanded.push('%');
+                expanded.push(token);
+            }
+            _ => expanded.push('%'),
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str, user: Option<&str>, port: Option<&str>) -> Host {
+        Host {
+            name: name.to_string(),
+            aliases: String::new(),
+            user: user.map(str::to_string),
+            destination: name.to_string(),
+            has_explicit_hostname: true,
+            port: port.map(str::to_string),
+            has_invalid_port: false,
+            proxy_command: None,
+            proxy_jump: None,
+            identity_agent: None,
+            address_family: None,
+            bind_address: None,
+            connect_timeout: None,
+            source_path: None,
+            label: None,
+            label_color: None,
+            tags: Vec::new(),
+            command_override: None,
+            description: None,
+            directives: BTreeSet::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    fn names(hosts: &[Host]) -> Vec<&str> {
+        hosts.iter().map(|host| host.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_config_explicit_hostname() {
+        let config = "Host anchor\n  User root\n\nHost server\n  Hostname server.example.com\n";
+        let hosts =
+            parse_config_reader(&mut config.as_bytes(), &ssh_config::Parser::new()).unwrap();
+
+        assert_eq!(hosts.len(), 2);
+
+        assert_eq!(hosts[0].name, "anchor");
+        assert_eq!(hosts[0].destination, "anchor");
+        assert!(!hosts[0].has_explicit_hostname);
+
+        assert_eq!(hosts[1].name, "server");
+        assert_eq!(hosts[1].destination, "server.example.com");
+        assert!(hosts[1].has_explicit_hostname);
+    }
+
+    #[test]
+    fn test_web_url() {
+        let config = "Host *.dashboard\n  #sshs web\n\nHost grafana.dashboard\n  Port 3000\n\nHost kibana.dashboard\n\nHost server\n";
+        let hosts =
+            parse_config_reader(&mut config.as_bytes(), &ssh_config::Parser::new()).unwrap();
+
+        let web_urls = hosts.iter().map(Host::web_url).collect::<Vec<_>>();
+        assert_eq!(
+            web_urls,
+            [
+                Some("https://grafana.dashboard:3000".to_string()),
+                Some("https://kibana.dashboard".to_string()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_score_tags() {
+        let config = "Host web\n  #sshs prod frontend\n\nHost db\n  #sshs Prod\n\nHost dev\n";
+        let hosts =
+            parse_config_reader(&mut config.as_bytes(), &ssh_config::Parser::new()).unwrap();
+
+        let matcher = SkimMatcherV2::default();
+        let matching = |search: &str| {
+            hosts
+                .iter()
+                .filter(|host| {
+                    host.search_score(&matcher, &[HostField::Name, HostField::Tags], search)
+                        .is_some()
+                })
+                .map(|host| host.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("#prod"), ["web", "db"]);
+        assert_eq!(matching("#prod w"), ["web"]);
+        assert_eq!(matching("#prod #frontend"), ["web"]);
+        assert_eq!(matching("#unknown"), Vec::<&str>::new());
+        assert_eq!(matching("front"), ["web"]);
+        assert_eq!(matching("#"), ["web", "db", "dev"]);
+        assert_eq!(matching("# front"), ["web"]);
+    }
+
+    #[test]
+    fn test_tag_directive() {
+        let hosts = parse_config_reader(
+            &mut "Host web\n  #sshs prod\n  Tag frontend\n\n\
+                   Host db\n  #sshs prod\n  Tag prod\n\n\
+                   Host *\n  Tag default\n"
+                .as_bytes(),
+            &ssh_config::Parser::new(),
+        )
+        .unwrap();
+
+        let tags = hosts
+            .iter()
+            .map(|host| (host.name.as_str(), host.tags.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [
+                ("web", vec!["prod".to_string(), "frontend".to_string()]),
+                ("db", vec!["prod".to_string()]),
+            ]
+        );
+
+        let matcher = SkimMatcherV2::default();
+        let web = &hosts[0];
+        assert!(web.matches_search(&matcher, &[HostField::Name], "#frontend"));
+        assert!(web.matches_search(&matcher, &[HostField::Tags], "front"));
+    }
+
+    #[test]
+    fn test_search_score_directives() {
+        let hosts = parse_config_reader(
+            &mut "Host web\n  ProxyJump bastion\n  IdentityFile ~/.ssh/web\n\nHost db\n  ProxyJump bastion\n\nHost dev\n"
+                .as_bytes(),
+            &ssh_config::Parser::new(),
+        )
+        .unwrap();
+
+        let matcher = SkimMatcherV2::default();
+        let matching = |search: &str| {
+            hosts
+                .iter()
+                .filter(|host| host.matches_search(&matcher, &[HostField::Name], search))
+                .map(|host| host.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("has:proxyjump"), ["web", "db"]);
+        assert_eq!(matching("HAS:ProxyJump d"), ["db"]);
+        assert_eq!(matching("has:proxyjump has:identityfile"), ["web"]);
+        assert_eq!(matching("has:hostname"), Vec::<&str>::new());
+        assert_eq!(matching("has:"), ["web", "db", "dev"]);
+    }
+
+    #[test]
+    fn test_search_score_name_prefix() {
+        let hosts = [
+            host("web", None, None),
+            host("Web-staging", None, None),
+            host("old-web", None, None),
+        ];
+
+        let matcher = SkimMatcherV2::default();
+        let matching = |search: &str| {
+            hosts
+                .iter()
+                .filter(|host| host.matches_search(&matcher, &[HostField::Name], search))
+                .map(|host| host.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("web"), ["web", "Web-staging", "old-web"]);
+        assert_eq!(matching("^web"), ["web", "Web-staging"]);
+        assert_eq!(matching("^web stg"), ["Web-staging"]);
+        assert_eq!(matching("^eb"), Vec::<&str>::new());
+        assert_eq!(matching("^"), ["web", "Web-staging", "old-web"]);
+        assert_eq!(matching("^ old"), ["old-web"]);
+    }
+
+    #[test]
+    fn test_matched_alias() {
+        let mut host = host("web", None, None);
+        host.aliases = "frontend, www, www-old".to_string();
+
+        let matcher = SkimMatcherV2::default();
+        assert_eq!(host.matched_alias(&matcher, "www"), Some("www"));
+        assert_eq!(
+            host.matched_alias(&matcher, "#prod front"),
+            Some("frontend")
+        );
+        assert_eq!(host.matched_alias(&matcher, "web"), None);
+        assert_eq!(host.matched_alias(&matcher, "db"), None);
+        assert_eq!(host.matched_alias(&matcher, "#prod"), None);
+    }
+
+    #[test]
+    fn test_ad_hoc() {
+        let host = Host::ad_hoc("root@example.com:2222").unwrap();
+        assert_eq!(host.name, "ssh://root@example.com:2222");
+        assert_eq!(host.user.as_deref(), Some("root"));
+        assert_eq!(host.destination, "example.com");
+        assert_eq!(host.port.as_deref(), Some("2222"));
+
+        let host = Host::ad_hoc("example.com").unwrap();
+        assert_eq!(host.name, "example.com");
+        assert_eq!(host.user, None);
+        assert_eq!(host.port, None);
+
+        assert_eq!(Host::ad_hoc("admin@::1").unwrap().name, "admin@::1");
+        assert_eq!(Host::ad_hoc("[::1]:22").unwrap().name, "ssh://[::1]:22");
+
+        for destination in [
+            "",
+            "web server",
+            "@example.com",
+            "example.com:",
+            "example.com:ssh",
+            "example.com:0",
+            "[::1",
+        ] {
+            assert!(Host::ad_hoc(destination).is_none(), "{destination:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_named() {
+        let config = "Host web www\n  Hostname example.com\n";
+        let hosts =
+            parse_config_reader(&mut config.as_bytes(), &ssh_config::Parser::new()).unwrap();
+
+        assert!(hosts[0].is_named("web"));
+        assert!(hosts[0].is_named("www"));
+        assert!(!hosts[0].is_named("ww"));
+        assert!(!hosts[0].is_named("example.com"));
+    }
+
+    #[test]
+    fn test_parse_config_no_merge() {
+        let config = "Host a\n  Hostname example.com\n\nHost b\n  Hostname example.com\n";
+
+        let merged =
+            parse_config_reader(&mut config.as_bytes(), &ssh_config::Parser::new()).unwrap();
+        let separate = parse_config_reader(
+            &mut config.as_bytes(),
+            &ssh_config::Parser::new().merge_same_hosts(false),
+        )
+        .unwrap();
+
+        assert_eq!(names(&merged), ["a"]);
+        assert_eq!(merged[0].aliases, "b");
+
+        assert_eq!(names(&separate), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_parse_include_dir() {
+        let directory = std::env::temp_dir().join("sshs-test-include-dir [config.d]");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("b"), "Host b\n  Hostname b.example.com\n").unwrap();
+        std::fs::write(directory.join("a"), "Host a\n  Hostname a.example.com\n").unwrap();
+
+        let hosts = parse_include_dir(&directory.display().to_string(), &ssh_config::Parser::new());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let hosts = hosts.unwrap();
+
+        assert_eq!(names(&hosts), ["a", "b"]);
+        assert_eq!(hosts[1].source_path, Some(directory.join("b")));
+    }
+
+    #[test]
+    fn test_resolve_host_entries() {
+        let path = std::env::temp_dir().join("sshs-test-resolve-host-entries");
+        std::fs::write(
+            &path,
+            "User admin\n\nHost web www\n  Hostname %h.example.com\n\nHost *\n  Port 2222\n",
+        )
+        .unwrap();
+
+        let config_paths = [path.display().to_string()];
+        let parser = ssh_config::Parser::new();
+        let www = resolve_host_entries(&config_paths, &[], &parser, "www").unwrap();
+        let unknown = resolve_host_entries(&config_paths, &[], &parser, "unknown").unwrap();
+        let hosts = parse_config_file(&path, &parser).unwrap();
+        std::fs::write(&path, "Host web\n  User admin\n").unwrap();
+        let not_found = resolve_host_entries(&config_paths, &[], &parser, "unknown").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let host = hosts.iter().find(|host| host.name == "www").unwrap();
+        let entries = www.as_ref().map(|entries| {
+            entries
+                .iter()
+                .map(|(key, value, _)| (key.clone(), value.clone()))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(Some(host.entries.clone()), entries);
+
+        assert_eq!(
+            www,
+            Some(vec![
+                ("hostname".to_string(), "www.example.com".to_string(), false),
+                ("port".to_string(), "2222".to_string(), true),
+                ("user".to_string(), "admin".to_string(), true),
+            ])
+        );
+        assert_eq!(
+            unknown,
+            Some(vec![
+                ("hostname".to_string(), "unknown".to_string(), false),
+                ("port".to_string(), "2222".to_string(), true),
+                ("user".to_string(), "admin".to_string(), true),
+            ])
+        );
+        assert_eq!(not_found, None);
+    }
+
+    #[test]
+    fn test_resolve_host_entries_pattern() {
+        let path = std::env::temp_dir().join("sshs-test-resolve-host-entries-pattern");
+        std::fs::write(
+            &path,
+            "User admin\n\nHost *.example.com\n  Port 2222\n\nHost *.dev\n  Port 22\n",
+        )
+        .unwrap();
+
+        let config_paths = [path.display().to_string()];
+        let parser = ssh_config::Parser::new();
+        let foo = resolve_host_entries(&config_paths, &[], &parser, "foo.example.com").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            foo,
+            Some(vec![
+                ("hostname".to_string(), "foo.example.com".to_string(), false),
+                ("port".to_string(), "2222".to_string(), true),
+                ("user".to_string(), "admin".to_string(), true),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let mut hosts = vec![
+            host("c", None, Some("2222")),
+            host("B", Some("root"), Some("22")),
+            host("a", Some("root"), None),
+            host("d", Some("admin"), Some("222")),
+        ];
+
+        SortBy::None.sort(&mut hosts, SortDir::Asc);
+        assert_eq!(names(&hosts), ["c", "B", "a", "d"]);
+
+        SortBy::Name.sort(&mut hosts, SortDir::Asc);
+        assert_eq!(names(&hosts), ["a", "B", "c", "d"]);
+
+        SortBy::User.sort(&mut hosts, SortDir::Asc);
+        assert_eq!(names(&hosts), ["d", "a", "B", "c"]);
+
+        SortBy::Port.sort(&mut hosts, SortDir::Asc);
+        assert_eq!(names(&hosts), ["B", "d", "c", "a"]);
+
+        SortBy::Port.sort(&mut hosts, SortDir::Desc);
+        assert_eq!(names(&hosts), ["a", "c", "d", "B"]);
+
+        SortBy::None.sort(&mut hosts, SortDir::Desc);
+        assert_eq!(names(&hosts), ["B", "d", "c", "a"]);
+    }
+
+    #[test]
+    fn test_normalize_port() {
+        assert_eq!(normalize_port("22"), ("22".to_string(), true));
+        assert_eq!(normalize_port("22 "), ("22".to_string(), true));
+        assert_eq!(normalize_port("\"2222\""), ("2222".to_string(), true));
+        assert_eq!(normalize_port("0022"), ("22".to_string(), true));
+
+        assert_eq!(normalize_port("0"), ("0".to_string(), false));
+        assert_eq!(normalize_port("65536"), ("65536".to_string(), false));
+        assert_eq!(normalize_port(" ssh "), ("ssh".to_string(), false));
+    }
+
+    #[test]
+    fn test_render_command_template_tokens() {
+        let template = "{{local_user}} {{remote_user}} {{home}}";
+
+        let root_host = host("web", Some("root"), None);
+        let mut context = TemplateContext::new(&root_host);
+        context.local_user = Some("alice".to_string());
+        context.home = "/home/alice".to_string();
+        let rendered = Handlebars::new().render_template(template, &context);
+        assert_eq!(rendered.unwrap(), "alice root /home/alice");
+
+        let host = host("web", None, Some("2222"));
+        let context = TemplateContext::new(&host);
+        assert_eq!(context.remote_user, context.local_user);
+
+        let (rendered, _) = host
+            .render_command_template("ssh -p {{port}} {{name}}", Launcher::None, &[])
+            .unwrap();
+        assert_eq!(rendered, "ssh -p 2222 web");
+    }
+
+    #[test]
+    fn test_render_command_template_extra_args() {
+        let host = host("web", None, None);
+        let extra_args = ["-o".to_string(), "StrictHostKeyChecking=no".to_string()];
+
+        let (rendered, args) = host
+            .render_command_template("ssh {{name}} uptime", Launcher::None, &extra_args)
+            .unwrap();
+        assert_eq!(rendered, "ssh web uptime");
+        assert_eq!(
+            args,
+            ["ssh", "-o", "StrictHostKeyChecking=no", "web", "uptime"]
+        );
+
+        let (_, args) = host
+            .render_command_template("mosh {{name}}", Launcher::None, &extra_args)
+            .unwrap();
+        assert_eq!(args, ["mosh", "web"]);
+    }
+
+    #[test]
+    fn test_split_windows_command() {
+        assert_eq!(
+            split_windows_command(r#"ssh "my server""#),
+            ["ssh", "my server"]
+        );
+        assert_eq!(
+            split_windows_command(r#"ssh -i C:\Users\me\.ssh\id_ed25519  "my server""#),
+            ["ssh", "-i", r"C:\Users\me\.ssh\id_ed25519", "my server"]
+        );
+        assert_eq!(
+            split_windows_command(r#""C:\Program Files\OpenSSH\ssh.exe" my\ server"#),
+            [r"C:\Program Files\OpenSSH\ssh.exe", r"my\", "server"]
+        );
+        assert_eq!(
+            split_windows_command(r#"echo "say \"hi\"" "a""b" "dir\\" """#),
+            ["echo", r#"say "hi""#, r#"a"b"#, r"dir\", ""]
+        );
+        assert_eq!(split_windows_command("ssh \"open"), ["ssh", "open"]);
+
+        let host = host("my server", None, None);
+        let rendered = TemplateContext::new(&host)
+            .render(DEFAULT_COMMAND_TEMPLATE)
+            .unwrap();
+        assert_eq!(split_windows_command(&rendered), ["ssh", "my server"]);
+        assert_eq!(shlex::split(&rendered).unwrap(), ["ssh", "my server"]);
+    }
+
+    #[test]
+    fn test_with_connect_timeout() {
+        assert_eq!(
+            with_connect_timeout("ssh \"{{{name}}}\""),
+            "ssh -o ConnectTimeout={{connect_timeout}} \"{{{name}}}\""
+        );
+        assert_eq!(
+            with_connect_timeout("/usr/bin/ssh"),
+            "/usr/bin/ssh -o ConnectTimeout={{connect_timeout}}"
+        );
+        assert_eq!(with_connect_timeout("mosh {{name}}"), "mosh {{name}}");
+        assert_eq!(
+            with_connect_timeout("sshpass ssh {{name}}"),
+            "sshpass ssh {{name}}"
+        );
+        for template in [
+            "ssh -o ConnectTimeout=3 {{name}}",
+            "ssh -oconnecttimeout=3 {{name}}",
+            "ssh -o \"ConnectTimeout 3\" {{name}}",
+            "ssh -o ConnectTimeout={{connect_timeout}} {{name}}",
+            "ssh {{#if connect_timeout}}-o ConnectTimeout=1 {{/if}}{{name}}",
+        ] {
+            assert_eq!(with_connect_timeout(template), template);
+        }
+
+        // Only the option and the variable count, not the text of the other arguments
+        assert_eq!(
+            with_connect_timeout("ssh {{name}} echo ConnectTimeout"),
+            "ssh -o ConnectTimeout={{connect_timeout}} {{name}} echo ConnectTimeout"
+        );
+        assert_eq!(
+            with_connect_timeout("ssh -o ConnectTimeoutMore=1 {{name}}"),
+            "ssh -o ConnectTimeout={{connect_timeout}} -o ConnectTimeoutMore=1 {{name}}"
+        );
+
+        let mut host = host("web", None, None);
+        host.connect_timeout = Some("5".to_string());
+        let (_, args) = host
+            .render_command_template(&with_connect_timeout("ssh {{name}}"), Launcher::None, &[])
+            .unwrap();
+        assert_eq!(args, ["ssh", "-o", "ConnectTimeout=5", "web"]);
+    }
+
+    #[test]
+    fn test_validate_template() {
+        assert!(TemplateContext::validate("ssh \"{{{name}}}\"").is_ok());
+        assert!(TemplateContext::validate("ssh {{#if port}}-p {{port}} {{/if}}{{name}}").is_ok());
+        assert!(TemplateContext::validate("ssh {{home}} {{remote_user}}").is_ok());
+
+        let err = TemplateContext::validate("ssh \"{{{nme}}}\"").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("`nme`"), "{message}");
+        assert!(message.contains("name, "), "{message}");
+        assert!(message.contains("destination"), "{message}");
+
+        assert!(TemplateContext::validate("ssh {{name").is_err());
+
+        let variables = |template: &str| TemplateContext::referenced_variables(template).unwrap();
+        assert_eq!(variables("ssh \"{{{nme}}}\""), ["nme"]);
+        assert_eq!(
+            variables("ssh {{#if port}}-p {{port}} {{else}}{{user}}{{/if}}{{name}} {{name}}"),
+            ["port", "user", "name"]
+        );
+        assert_eq!(
+            variables("{{#each tags}}{{this}}{{/each}} {{lookup tags 0}} {{host.name}}"),
+            ["tags", "host"]
+        );
+        assert!(variables("ssh example.com").is_empty());
+        assert!(TemplateContext::referenced_variables("ssh {{name").is_err());
+
+        let (rendered, _) = host("web", None, None)
+            .render_command_template("ssh {{user}}{{name}}", Launcher::None, &[])
+            .unwrap();
+        assert_eq!(rendered, "ssh web");
+    }
+
+    #[test]
+    fn test_address() {
+        for (destination, port, address) in [
+            ("192.0.2.1", None, "192.0.2.1"),
+            ("192.0.2.1", Some("2222"), "192.0.2.1:2222"),
+            ("2001:db8::1", None, "2001:db8::1"),
+            ("2001:db8::1", Some("2222"), "[2001:db8::1]:2222"),
+            ("fe80::1%eth0", Some("22"), "[fe80::1%eth0]:22"),
+            ("web.example.com", None, "web.example.com"),
+            ("web.example.com", Some("2222"), "web.example.com:2222"),
+        ] {
+            let host = Host {
+                destination: destination.to_string(),
+                ..host("web", None, port)
+            };
+            assert_eq!(host.address(), address);
+        }
+
+        let mut host = Host {
+            destination: "2001:db8::1".to_string(),
+            ..host("web", Some("root"), Some("2222"))
+        };
+        let (rendered, _) = host
+            .render_command_template("ssh ssh://{{user}}@{{address}}", Launcher::None, &[])
+            .unwrap();
+        assert_eq!(rendered, "ssh ssh://root@[2001:db8::1]:2222");
+
+        host.tags = vec![WEB_TAG.to_string()];
+        host.port = None;
+        assert_eq!(host.web_url().unwrap(), "https://[2001:db8::1]");
+    }
+
+    #[test]
+    fn test_proxy_chain() {
+        let with_proxy = |name: &str, proxy_jump: Option<&str>, proxy_command: Option<&str>| Host {
+            proxy_jump: proxy_jump.map(str::to_string),
+            proxy_command: proxy_command.map(str::to_string),
+            ..host(name, None, None)
+        };
+        let jump = |name: &str, destination: Option<&str>| ProxyHop::Jump {
+            name: name.to_string(),
+            destination: destination.map(str::to_string),
+        };
+
+        let hosts = [
+            with_proxy("web", Some("admin@bastion:2222"), None),
+            with_proxy("bastion", Some("gateway,missing"), None),
+            with_proxy("gateway", None, Some("nc -X 5 -x proxy:1080 %h %p")),
+            with_proxy("db", Some("loop"), None),
+            with_proxy("loop", Some("db"), None),
+            with_proxy("direct", Some("none"), None),
+        ];
+
+        assert_eq!(
+            hosts[0].proxy_chain(&hosts),
+            [
+                jump("admin@bastion:2222", Some("bastion")),
+                jump("missing", None),
+                jump("gateway", Some("gateway")),
+                ProxyHop::Command("nc -X 5 -x proxy:1080 %h %p".to_string()),
+            ]
+        );
+        assert_eq!(
+            hosts[3].proxy_chain(&hosts),
+            [
+                jump("loop", Some("loop")),
+                ProxyHop::Cycle("db".to_string())
+            ]
+        );
+        assert!(hosts[5].proxy_chain(&hosts).is_empty());
+
+        assert_eq!(jump_host_name("ssh://user@[::1]:22"), "::1");
+        assert_eq!(jump_host_name("bastion"), "bastion");
+    }
+
+    #[test]
+    fn test_expand_identity_agent() {
+        std::env::set_var("SSHS_TEST_AGENT_DIR", "/run/agents");
+        let home = shellexpand::tilde("~").into_owned();
+
+        assert_eq!(
+            expand_identity_agent("${SSHS_TEST_AGENT_DIR}/gpg.sock"),
+            "/run/agents/gpg.sock"
+        );
+        assert_eq!(
+            expand_identity_agent("\"~/.1password/agent.sock\""),
+            format!("{home}/.1password/agent.sock")
+        );
+        assert_eq!(expand_identity_agent("none"), "none");
+        assert_eq!(
+            expand_identity_agent("${SSHS_TEST_UNSET_VARIABLE}/agent.sock"),
+            "${SSHS_TEST_UNSET_VARIABLE}/agent.sock"
+        );
+    }
+
+    #[test]
+    fn test_expand_hostname_tokens() {
+        assert_eq!(
+            expand_hostname_tokens("%h.internal.example.com", "web"),
+            "web.internal.example.com"
+        );
+        assert_eq!(
+            expand_hostname_tokens("%n.example.com", "db"),
+            "db.example.com"
+        );
+        assert_eq!(expand_hostname_tokens("example.com", "web"), "example.com");
+    }
+
+    #[test]
+    fn test_expand_hostname_tokens_literal_percent() {
+        assert_eq!(
+            expand_hostname_tokens("100%%.example.com", "web"),
+            "100%.example.com"
+        );
+        assert_eq!(expand_hostname_tokens("%%h", "web"), "%h");
+        assert_eq!(expand_hostname_tokens("%h:%p", "web"), "web:%p");
+        assert_eq!(expand_hostname_tokens("trailing%", "web"), "trailing%");
+    }
+}
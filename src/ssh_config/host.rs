@@ -1,5 +1,6 @@
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use super::EntryType;
 
@@ -9,6 +10,12 @@ pub(crate) type Entry = (EntryType, String);
 pub struct Host {
     patterns: Vec<String>,
     entries: HashMap<EntryType, String>,
+    source_path: Option<PathBuf>,
+    tags: Vec<String>,
+    command: Option<String>,
+    description: Option<String>,
+    /// Entries inherited from the global options or from pattern hosts.
+    inherited: HashSet<EntryType>,
 }
 
 impl Host {
@@ -17,10 +24,45 @@ impl Host {
         Host {
             patterns,
             entries: HashMap::new(),
+            source_path: None,
+            tags: Vec::new(),
+            command: None,
+            description: None,
+            inherited: HashSet::new(),
         }
     }
 
+    /// Sets the path of the file the host is defined in.
+    #[must_use]
+    pub fn with_source_path(mut self, source_path: Option<PathBuf>) -> Host {
+        self.source_path = source_path;
+        self
+    }
+
+    /// Adds the tags that aren't already given to the host.
+    pub fn add_tags<I>(&mut self, tags: I)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for tag in tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// Sets the command annotation of the host, the last one written in the block wins.
+    pub fn set_command(&mut self, command: String) {
+        self.command = Some(command);
+    }
+
+    /// Sets the description of the host, written as a comment of its block.
+    pub fn set_description(&mut self, description: String) {
+        self.description = Some(description);
+    }
+
     pub fn update(&mut self, entry: Entry) {
+        self.inherited.remove(&entry.0);
         self.entries.insert(entry.0, entry.1);
     }
 
@@ -28,7 +70,15 @@ impl Host {
         self.patterns.extend(host.patterns.clone());
     }
 
+    /// Adds the entries of the host, an entry staying inherited if both hosts inherited it.
     pub(crate) fn extend_entries(&mut self, host: &Host) {
+        for key in host.entries.keys() {
+            if !host.inherited.contains(key) {
+                self.inherited.remove(key);
+            } else if !self.entries.contains_key(key) {
+                self.inherited.insert(key.clone());
+            }
+        }
         self.entries.extend(host.entries.clone());
     }
 
@@ -36,8 +86,14 @@ impl Host {
         for (key, value) in &host.entries {
             if !self.entries.contains_key(key) {
                 self.entries.insert(key.clone(), value.clone());
+                self.inherited.insert(key.clone());
             }
         }
+
+        self.add_tags(host.tags.iter().cloned());
+        if self.command.is_none() {
+            self.command.clone_from(&host.command);
+        }
     }
 
     #[allow(clippy::must_use_candidate)]
@@ -45,9 +101,46 @@ impl Host {
         &self.patterns
     }
 
-    /// # Panics
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_entries(&self) -> &HashMap<EntryType, String> {
+        &self.entries
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_source_path(&self) -> Option<&Path> {
+        self.source_path.as_deref()
+    }
+
+    /// Returns `true` if the entry comes from the global options or from a pattern host
+    /// (e.g. `Host *`) rather than from the host block itself.
+    #[allow(clippy::must_use_candidate)]
+    pub fn is_inherited(&self, entry: &EntryType) -> bool {
+        self.inherited.contains(entry)
+    }
+
+    /// Tags given by `#sshs` annotations, see [`Parser`](super::Parser).
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Command given by a `# sshs-command:` annotation, see [`Parser`](super::Parser).
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_command(&self) -> Option<&str> {
+        self.command.as_deref()
+    }
+
+    /// Description given by the comment of the `Host` line or the first comment of the block,
+    /// see [`Parser`](super::Parser). Pattern hosts don't give theirs to the hosts they apply to.
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Regexes of the glob patterns, with whether they are negated.
     ///
-    /// Will panic if the regex cannot be compiled.
+    /// Like in ssh, they match host names case-insensitively, and a pattern that doesn't compile
+    /// (e.g. the reversed range of `web[z-a]`) matches nothing.
     #[allow(clippy::must_use_candidate)]
     pub fn matching_pattern_regexes(&self) -> Vec<(Regex, bool)> {
         if self.patterns.is_empty() {
@@ -57,28 +150,47 @@ impl Host {
         self.patterns
             .iter()
             .filter_map(|pattern| {
-                let contains_wildcard =
-                    pattern.contains('*') || pattern.contains('?') || pattern.contains('!');
-                if !contains_wildcard {
+                if !is_pattern(pattern) {
                     return None;
                 }
 
-                let mut pattern = pattern
-                    .replace('.', r"\.")
-                    .replace('*', ".*")
-                    .replace('?', ".");
+                let (is_negated, pattern) = match pattern.strip_prefix('!') {
+                    Some(pattern) => (true, pattern),
+                    None => (false, pattern.as_str()),
+                };
 
-                let is_negated = pattern.starts_with('!');
-                if is_negated {
-                    pattern.remove(0);
-                }
-
-                pattern = format!("^{pattern}$");
-                Some((Regex::new(&pattern).unwrap(), is_negated))
+                let pattern = format!("(?i)^{}$", glob_to_regex(pattern));
+                Regex::new(&pattern).ok().map(|regex| (regex, is_negated))
             })
             .collect()
     }
 
+    /// Returns `true` if `name` matches at least one positive pattern and none of the negated ones,
+    /// `regexes` being the [`Host::matching_pattern_regexes`].
+    ///
+    /// Like ssh, a host with only negated patterns (e.g. `Host !jumpbox`) matches no name.
+    #[must_use]
+    pub fn matches(&self, regexes: &[(Regex, bool)], name: &str) -> bool {
+        if regexes
+            .iter()
+            .any(|(regex, is_negated)| *is_negated && regex.is_match(name))
+        {
+            return false;
+        }
+
+        self.patterns
+            .iter()
+            .filter(|pattern| !is_pattern(pattern))
+            .map(|pattern| pattern.eq_ignore_ascii_case(name))
+            .chain(
+                regexes
+                    .iter()
+                    .filter(|(_, is_negated)| !is_negated)
+                    .map(|(regex, _)| regex.is_match(name)),
+            )
+            .any(|is_match| is_match)
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub fn get(&self, entry: &EntryType) -> Option<String> {
         self.entries.get(entry).cloned()
@@ -90,6 +202,64 @@ impl Host {
     }
 }
 
+/// Returns `true` if the host pattern has to be matched against other hosts,
+/// either because it contains a wildcard or because it is negated (e.g. `!db.example.com`).
+fn is_pattern(pattern: &str) -> bool {
+    pattern.starts_with('!') || pattern.contains(['*', '?']) || has_character_class(pattern)
+}
+
+fn has_character_class(pattern: &str) -> bool {
+    pattern
+        .find('[')
+        .is_some_and(|start| pattern[start + 1..].contains(']'))
+}
+
+/// Translates the glob into a regex, `*`, `?` and character classes (`[a-z]`, `[!0-9]`) being
+/// the only special characters.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                let rest = chars.clone().collect::<String>();
+                let class = rest.split_once(']').map_or("", |(class, _)| class);
+                let (is_negated, class_chars) = match class.strip_prefix(['!', '^']) {
+                    Some(class_chars) => (true, class_chars),
+                    None => (false, class),
+                };
+                if class_chars.is_empty() {
+                    regex.push_str(r"\[");
+                    continue;
+                }
+
+                // Skip the class and its closing bracket
+                chars.nth(class.chars().count());
+
+                regex.push('[');
+                if is_negated {
+                    regex.push('^');
+                }
+                for c in class_chars.chars() {
+                    // Only ranges keep their meaning inside the class
+                    if c == '-' {
+                        regex.push(c);
+                    } else {
+                        regex.push_str(&regex::escape(&c.to_string()));
+                    }
+                }
+                regex.push(']');
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub trait HostVecExt {
     /// Apply the name entry to the hostname entry if the hostname entry is empty.
@@ -134,7 +304,11 @@ impl HostVecExt for Vec<Host> {
             for j in (0..i).rev() {
                 let target_host = &mut left[j];
 
-                if current_host.entries != target_host.entries {
+                if current_host.entries != target_host.entries
+                    || current_host.tags != target_host.tags
+                    || current_host.command != target_host.command
+                    || current_host.description != target_host.description
+                {
                     continue;
                 }
 
@@ -143,9 +317,19 @@ impl HostVecExt for Vec<Host> {
                     .values()
                     .any(|value| value.contains("%h"))
                 {
+                    log::trace!(
+                        "Not merging `{}` into `{}`: its entries depend on its name (%h)",
+                        current_host.patterns.join(" "),
+                        target_host.patterns.join(" ")
+                    );
                     continue;
                 }
 
+                log::debug!(
+                    "Merging `{}` into `{}`: same entries",
+                    current_host.patterns.join(" "),
+                    target_host.patterns.join(" ")
+                );
                 target_host.extend_patterns(current_host);
                 target_host.extend_entries(current_host);
                 hosts.remove(i);
@@ -180,43 +364,51 @@ impl HostVecExt for Vec<Host> {
     ///
     /// You might want to call [`HostVecExt::merge_same_hosts`] after this.
     fn apply_patterns(&self) -> Self {
-        let mut hosts = self.spread();
-        let mut pattern_indexes = Vec::new();
-
-        for i in 0..hosts.len() {
-            let matching_pattern_regexes = hosts[i].matching_pattern_regexes();
-            if matching_pattern_regexes.is_empty() {
+        let mut hosts = Vec::new();
+        for host in self {
+            let literal_patterns = host
+                .patterns
+                .iter()
+                .filter(|pattern| !is_pattern(pattern))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if literal_patterns.len() == host.patterns.len() {
+                hosts.extend(vec![host.clone()].spread());
                 continue;
             }
 
-            pattern_indexes.push(i);
-
-            for j in 0..hosts.len() {
-                if i == j {
-                    continue;
-                }
-
-                if !hosts[j].matching_pattern_regexes().is_empty() {
-                    continue;
-                }
-
-                for (regex, is_negated) in &matching_pattern_regexes {
-                    if regex.is_match(&hosts[j].patterns[0]) == *is_negated {
-                        continue;
-                    }
+            // Keep the pattern host whole and spread a host for each literal pattern,
+            // e.g. `Host web *.example.com` also defines the `web` host
+            hosts.push(host.clone());
+            for pattern in literal_patterns {
+                let mut new_host = host.clone();
+                new_host.patterns = vec![pattern];
+                hosts.push(new_host);
+            }
+        }
 
-                    let host = hosts[i].clone();
-                    hosts[j].extend_if_not_contained(&host);
-                    break;
-                }
+        // The regexes are compiled once per pattern host, not once per pair of hosts
+        let mut pattern_hosts = Vec::new();
+        let mut concrete_hosts = Vec::new();
+        for host in hosts {
+            if host.patterns.iter().any(|pattern| is_pattern(pattern)) {
+                let regexes = host.matching_pattern_regexes();
+                pattern_hosts.push((host, regexes));
+            } else {
+                concrete_hosts.push(host);
             }
         }
 
-        for i in pattern_indexes.into_iter().rev() {
-            hosts.remove(i);
+        for (pattern_host, regexes) in &pattern_hosts {
+            for host in &mut concrete_hosts {
+                if pattern_host.matches(regexes, &host.patterns[0]) {
+                    host.extend_if_not_contained(pattern_host);
+                }
+            }
         }
 
-        hosts
+        concrete_hosts
     }
 }
 
@@ -232,7 +424,8 @@ mod tests {
         host.update((EntryType::Hostname, "example.com".to_string()));
         hosts.push(host);
 
-        let mut host = Host::new(vec!["!example.com".to_string()]);
+        // Like ssh, the negated pattern needs a positive one to match anything
+        let mut host = Host::new(vec!["*".to_string(), "!example.com".to_string()]);
         host.update((EntryType::User, "hello".to_string()));
         hosts.push(host);
 
@@ -262,6 +455,210 @@ mod tests {
         assert_eq!(hosts[1].entries[&EntryType::Port], "22");
     }
 
+    #[test]
+    fn test_matching_pattern_regexes_negated_literal() {
+        let host = Host::new(vec![
+            "*.example.com".to_string(),
+            "!db.example.com".to_string(),
+            "web.example.com".to_string(),
+        ]);
+
+        let regexes = host.matching_pattern_regexes();
+
+        assert_eq!(regexes.len(), 2);
+
+        assert!(!regexes[0].1);
+        assert!(regexes[0].0.is_match("db.example.com"));
+        assert!(regexes[0].0.is_match("web.example.com"));
+
+        assert!(regexes[1].1);
+        assert!(regexes[1].0.is_match("db.example.com"));
+        assert!(!regexes[1].0.is_match("dbxexample.com"));
+        assert!(!regexes[1].0.is_match("web.example.com"));
+    }
+
+    #[test]
+    fn test_apply_patterns_glob_and_negated_literal() {
+        let mut hosts = Vec::new();
+
+        let mut host = Host::new(vec![
+            "*.example.com".to_string(),
+            "!db.example.com".to_string(),
+        ]);
+        host.update((EntryType::User, "admin".to_string()));
+        hosts.push(host);
+
+        hosts.push(Host::new(vec!["db.example.com".to_string()]));
+        hosts.push(Host::new(vec!["web.example.com".to_string()]));
+        hosts.push(Host::new(vec!["other.org".to_string()]));
+
+        let hosts = hosts.apply_patterns();
+
+        assert_eq!(hosts.len(), 3);
+
+        assert_eq!(hosts[0].patterns[0], "db.example.com");
+        assert!(hosts[0].entries.is_empty());
+
+        assert_eq!(hosts[1].patterns[0], "web.example.com");
+        assert_eq!(hosts[1].entries.len(), 1);
+        assert_eq!(hosts[1].entries[&EntryType::User], "admin");
+
+        assert_eq!(hosts[2].patterns[0], "other.org");
+        assert!(hosts[2].entries.is_empty());
+    }
+
+    #[test]
+    fn test_apply_patterns_only_negated() {
+        let mut host = Host::new(vec!["!foo".to_string()]);
+        host.update((EntryType::User, "neg".to_string()));
+
+        let hosts = vec![host, Host::new(vec!["bar".to_string()])].apply_patterns();
+
+        assert_eq!(hosts.len(), 1);
+        assert!(hosts[0].entries.is_empty());
+    }
+
+    #[test]
+    fn test_apply_patterns_mixed_negation() {
+        let mut hosts = Vec::new();
+
+        let mut host = Host::new(vec!["*".to_string(), "!jumpbox".to_string()]);
+        host.update((EntryType::ProxyJump, "jumpbox".to_string()));
+        hosts.push(host);
+
+        let mut host = Host::new(vec!["web".to_string(), "*.example.com".to_string()]);
+        host.update((EntryType::User, "admin".to_string()));
+        hosts.push(host);
+
+        hosts.push(Host::new(vec!["jumpbox".to_string()]));
+        hosts.push(Host::new(vec!["db.example.com".to_string()]));
+
+        let hosts = hosts.apply_patterns();
+
+        assert_eq!(hosts.len(), 3);
+
+        assert_eq!(hosts[0].patterns, ["web"]);
+        assert_eq!(hosts[0].entries.len(), 2);
+        assert_eq!(hosts[0].entries[&EntryType::ProxyJump], "jumpbox");
+        assert_eq!(hosts[0].entries[&EntryType::User], "admin");
+
+        assert_eq!(hosts[1].patterns, ["jumpbox"]);
+        assert!(hosts[1].entries.is_empty());
+
+        assert_eq!(hosts[2].patterns, ["db.example.com"]);
+        assert_eq!(hosts[2].entries.len(), 2);
+        assert_eq!(hosts[2].entries[&EntryType::ProxyJump], "jumpbox");
+        assert_eq!(hosts[2].entries[&EntryType::User], "admin");
+    }
+
+    #[test]
+    fn test_apply_patterns_case_insensitive() {
+        let mut hosts = Vec::new();
+
+        let mut host = Host::new(vec!["Prod*".to_string(), "!PROD-DB".to_string()]);
+        host.update((EntryType::User, "admin".to_string()));
+        hosts.push(host);
+
+        let mut host = Host::new(vec!["*.DEV".to_string()]);
+        host.update((EntryType::Port, "2222".to_string()));
+        hosts.push(host);
+
+        hosts.push(Host::new(vec!["prod-web".to_string()]));
+        hosts.push(Host::new(vec!["prod-db".to_string()]));
+        hosts.push(Host::new(vec!["staging.dev".to_string()]));
+
+        let hosts = hosts.apply_patterns();
+
+        assert_eq!(hosts.len(), 3);
+
+        assert_eq!(hosts[0].patterns, ["prod-web"]);
+        assert_eq!(hosts[0].entries[&EntryType::User], "admin");
+
+        assert_eq!(hosts[1].patterns, ["prod-db"]);
+        assert!(hosts[1].entries.is_empty());
+
+        assert_eq!(hosts[2].patterns, ["staging.dev"]);
+        assert_eq!(hosts[2].entries[&EntryType::Port], "2222");
+    }
+
+    #[test]
+    fn test_matching_pattern_regexes_character_class() {
+        let host = Host::new(vec!["web[1-3]".to_string()]);
+        let regexes = host.matching_pattern_regexes();
+        assert_eq!(regexes.len(), 1);
+
+        for name in ["web1", "web2", "web3"] {
+            assert!(host.matches(&regexes, name), "{name}");
+        }
+        for name in ["web4", "web", "web12", "web[1-3]"] {
+            assert!(!host.matches(&regexes, name), "{name}");
+        }
+
+        let host = Host::new(vec![
+            "db[!0-9].example.com".to_string(),
+            "api[.".to_string(),
+        ]);
+        let regexes = host.matching_pattern_regexes();
+        assert!(host.matches(&regexes, "dbx.example.com"));
+        assert!(!host.matches(&regexes, "db1.example.com"));
+        assert!(!host.matches(&regexes, "dbxxexample.com"));
+        assert!(host.matches(&regexes, "api[."));
+        assert!(!host.matches(&regexes, "apix."));
+
+        assert_eq!(glob_to_regex("web[!]"), r"web\[!\]");
+    }
+
+    #[test]
+    fn test_matching_pattern_regexes_invalid_class() {
+        let host = Host::new(vec!["web[z-a]".to_string()]);
+        let regexes = host.matching_pattern_regexes();
+        assert!(regexes.is_empty());
+        for name in ["webq", "web[z-a]", "web"] {
+            assert!(!host.matches(&regexes, name), "{name}");
+        }
+
+        let host = Host::new(vec!["web[".to_string()]);
+        let regexes = host.matching_pattern_regexes();
+        assert!(regexes.is_empty());
+        assert!(host.matches(&regexes, "web["));
+        assert!(!host.matches(&regexes, "web"));
+
+        let mut hosts = Vec::new();
+        let mut host = Host::new(vec!["web[z-a]".to_string()]);
+        host.update((EntryType::User, "admin".to_string()));
+        hosts.push(host);
+        hosts.push(Host::new(vec!["webq".to_string()]));
+
+        let hosts = hosts.apply_patterns();
+        assert_eq!(hosts.len(), 1);
+        assert!(hosts[0].get(&EntryType::User).is_none());
+    }
+
+    #[test]
+    fn test_inherited_entries() {
+        let mut hosts = Vec::new();
+
+        let mut host = Host::new(vec!["*.example.com".to_string()]);
+        host.update((EntryType::User, "admin".to_string()));
+        host.update((EntryType::Port, "2222".to_string()));
+        hosts.push(host);
+
+        let mut host = Host::new(vec!["web.example.com".to_string()]);
+        host.update((EntryType::Port, "22".to_string()));
+        hosts.push(host);
+
+        let hosts = hosts.apply_patterns();
+
+        assert_eq!(hosts.len(), 1);
+        assert!(hosts[0].is_inherited(&EntryType::User));
+        assert!(!hosts[0].is_inherited(&EntryType::Port));
+        assert!(!hosts[0].is_inherited(&EntryType::Hostname));
+
+        let mut host = hosts[0].clone();
+        host.update((EntryType::User, "root".to_string()));
+        assert!(!host.is_inherited(&EntryType::User));
+    }
+
     #[test]
     fn test_spread() {
         let mut hosts = Vec::new();
@@ -1,7 +1,18 @@
 use strum_macros;
 
-/// List from <https://man7.org/linux/man-pages/man5/ssh_config.5.html>
-#[derive(Debug, strum_macros::Display, strum_macros::EnumString, Eq, PartialEq, Hash, Clone)]
+/// List from <https://man7.org/linux/man-pages/man5/ssh_config.5.html>, parsed whatever their
+/// case like ssh does. The former names ssh still accepts are parsed as the directive replacing
+/// them, which is the name they are displayed with.
+#[derive(
+    Debug,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+    Eq,
+    PartialEq,
+    Hash,
+    Clone,
+)]
 #[strum(ascii_case_insensitive)]
 pub enum EntryType {
     #[strum(disabled)]
@@ -46,6 +57,11 @@ pub enum EntryType {
     GSSAPIAuthentication,
     GSSAPIDelegateCredentials,
     HashKnownHosts,
+    #[strum(
+        to_string = "HostbasedAcceptedAlgorithms",
+        serialize = "HostbasedAcceptedKeyTypes",
+        serialize = "HostbasedKeyTypes"
+    )]
     HostbasedAcceptedAlgorithms,
     HostbasedAuthentication,
     HostKeyAlgorithms,
@@ -53,10 +69,17 @@ pub enum EntryType {
     Hostname,
     IdentitiesOnly,
     IdentityAgent,
+    #[strum(to_string = "IdentityFile", serialize = "IdentityFile2")]
     IdentityFile,
     IgnoreUnknown,
     Include,
     IPQoS,
+    #[strum(
+        to_string = "KbdInteractiveAuthentication",
+        serialize = "ChallengeResponseAuthentication",
+        serialize = "SkeyAuthentication",
+        serialize = "TisAuthentication"
+    )]
     KbdInteractiveAuthentication,
     KbdInteractiveDevices,
     KexAlgorithms,
@@ -78,7 +101,12 @@ pub enum EntryType {
     ProxyCommand,
     ProxyJump,
     ProxyUseFdpass,
+    #[strum(
+        to_string = "PubkeyAcceptedAlgorithms",
+        serialize = "PubkeyAcceptedKeyTypes"
+    )]
     PubkeyAcceptedAlgorithms,
+    #[strum(to_string = "PubkeyAuthentication", serialize = "DSAAuthentication")]
     PubkeyAuthentication,
     RekeyLimit,
     RemoteCommand,
@@ -103,8 +131,69 @@ pub enum EntryType {
     TunnelDevice,
     UpdateHostKeys,
     User,
+    /// Apple-specific, see <https://developer.apple.com/library/archive/technotes/tn2449/_index.html>
+    UseKeychain,
     UserKnownHostsFile,
     VerifyHostKeyDNS,
     VisualHostKey,
     XAuthLocation,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_boolean_directives() {
+        let directives = [
+            ("AddKeysToAgent", EntryType::AddKeysToAgent),
+            ("identitiesonly", EntryType::IdentitiesOnly),
+            ("ServerAliveInterval", EntryType::ServerAliveInterval),
+            ("SERVERALIVECOUNTMAX", EntryType::ServerAliveCountMax),
+            ("Compression", EntryType::Compression),
+            ("StrictHostKeyChecking", EntryType::StrictHostKeyChecking),
+            ("UseKeychain", EntryType::UseKeychain),
+        ];
+
+        for (directive, entry_type) in directives {
+            assert_eq!(EntryType::from_str(directive), Ok(entry_type));
+        }
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        for directive in ["Hostname", "HostName", "hostname", "HOSTNAME"] {
+            assert_eq!(EntryType::from_str(directive), Ok(EntryType::Hostname));
+        }
+        assert_eq!(EntryType::Hostname.to_string(), "Hostname");
+        assert!(EntryType::from_str("Host-Name").is_err());
+    }
+
+    #[test]
+    fn test_former_names() {
+        let directives = [
+            (
+                "PubkeyAcceptedKeyTypes",
+                EntryType::PubkeyAcceptedAlgorithms,
+            ),
+            (
+                "pubkeyacceptedalgorithms",
+                EntryType::PubkeyAcceptedAlgorithms,
+            ),
+            ("HostbasedKeyTypes", EntryType::HostbasedAcceptedAlgorithms),
+            (
+                "ChallengeResponseAuthentication",
+                EntryType::KbdInteractiveAuthentication,
+            ),
+            ("DSAAuthentication", EntryType::PubkeyAuthentication),
+            ("identityfile2", EntryType::IdentityFile),
+        ];
+
+        for (directive, entry_type) in directives {
+            let parsed = EntryType::from_str(directive).unwrap();
+            assert_eq!(parsed.to_string(), entry_type.to_string());
+            assert_eq!(parsed, entry_type);
+        }
+    }
+}
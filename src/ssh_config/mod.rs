@@ -2,6 +2,7 @@ pub mod host;
 mod host_entry;
 pub mod parser;
 pub mod parser_error;
+pub mod writer;
 
 pub use host::Host;
 pub use host::HostVecExt;
@@ -2,19 +2,34 @@ use glob::glob;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Mutex;
 
 use super::host::Entry;
 use super::parser_error::InvalidIncludeError;
 use super::parser_error::InvalidIncludeErrorDetails;
+use super::parser_error::Location;
 use super::parser_error::ParseError;
 use super::parser_error::UnknownEntryError;
+use super::parser_error::UnparseableLineError;
 use super::{EntryType, Host};
 
+/// Prefix of the `# @tags: tag1 tag2` annotation comments.
+pub const DEFAULT_TAGS_ANNOTATION_PREFIX: &str = "@tags:";
+
+/// Prefix of the `# sshs-command: mosh {{{name}}}` annotation comments.
+pub const COMMAND_ANNOTATION_PREFIX: &str = "sshs-command:";
+
 #[derive(Debug)]
 pub struct Parser {
     ignore_unknown_entries: bool,
+    ignore_missing_includes: bool,
+    merge_same_hosts: bool,
+    tags_annotation_prefix: String,
+    include_allow_roots: Vec<PathBuf>,
+    /// Canonicalized paths of the files opened by `Include` lines, see [`Parser::included_paths`].
+    included_paths: Mutex<Vec<PathBuf>>,
 }
 
 impl Default for Parser {
@@ -28,9 +43,72 @@ impl Parser {
     pub fn new() -> Parser {
         Parser {
             ignore_unknown_entries: true,
+            ignore_missing_includes: true,
+            merge_same_hosts: true,
+            tags_annotation_prefix: DEFAULT_TAGS_ANNOTATION_PREFIX.to_string(),
+            include_allow_roots: Vec::new(),
+            included_paths: Mutex::new(Vec::new()),
         }
     }
 
+    /// Sets whether unknown entries are ignored or make the parsing fail (`true` by default).
+    #[must_use]
+    pub fn ignore_unknown_entries(mut self, ignore_unknown_entries: bool) -> Parser {
+        self.ignore_unknown_entries = ignore_unknown_entries;
+        self
+    }
+
+    /// Sets whether the `Include` lines of missing files are skipped with a warning, like ssh
+    /// does, or fail with an [`InvalidIncludeError`](super::parser_error::InvalidIncludeError)
+    /// (`true` by default). Patterns matching no file are always skipped.
+    #[must_use]
+    pub fn ignore_missing_includes(mut self, ignore_missing_includes: bool) -> Parser {
+        self.ignore_missing_includes = ignore_missing_includes;
+        self
+    }
+
+    /// Sets whether hosts with the same entries are resolved as a single host, their other names
+    /// becoming aliases (`true` by default).
+    #[must_use]
+    pub fn merge_same_hosts(mut self, merge_same_hosts: bool) -> Parser {
+        self.merge_same_hosts = merge_same_hosts;
+        self
+    }
+
+    /// Restricts the included files to the ones inside these directories, symlinks resolved.
+    /// Any file can be included when empty (by default).
+    #[must_use]
+    pub fn include_allow_roots(mut self, roots: &[PathBuf]) -> Parser {
+        self.include_allow_roots = roots
+            .iter()
+            .map(|root| std::fs::canonicalize(root).unwrap_or_else(|_| root.clone()))
+            .collect();
+        self
+    }
+
+    #[allow(clippy::must_use_candidate)]
+    pub fn get_merge_same_hosts(&self) -> bool {
+        self.merge_same_hosts
+    }
+
+    /// Canonicalized paths of the files included by the configurations parsed so far, once each
+    /// in the order they were opened, including the ones defining no host.
+    #[must_use]
+    pub fn included_paths(&self) -> Vec<PathBuf> {
+        self.included_paths
+            .lock()
+            .map(|paths| paths.clone())
+            .unwrap_or_default()
+    }
+
+    /// Sets the prefix of the `# <prefix> tag1 tag2` tags annotation comments
+    /// ([`DEFAULT_TAGS_ANNOTATION_PREFIX`] by default), `#sshs tag1 tag2` comments always work.
+    #[must_use]
+    pub fn tags_annotation_prefix(mut self, tags_annotation_prefix: &str) -> Parser {
+        self.tags_annotation_prefix = tags_annotation_prefix.to_string();
+        self
+    }
+
     /// # Errors
     ///
     /// Will return `Err` if the SSH configuration cannot be parsed.
@@ -38,106 +116,161 @@ impl Parser {
     where
         P: AsRef<Path>,
     {
-        let mut reader = BufReader::new(File::open(path)?);
-        self.parse(&mut reader)
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut includes = Includes {
+            stack: vec![std::fs::canonicalize(&path)?],
+            ..Includes::default()
+        };
+        let (global_host, hosts) = self.parse_raw(&mut reader, &mut includes)?;
+
+        Ok(apply_global_host(&global_host, hosts))
     }
 
     /// # Errors
     ///
     /// Will return `Err` if the SSH configuration cannot be parsed.
     pub fn parse(&self, reader: &mut impl BufRead) -> Result<Vec<Host>, ParseError> {
-        let (global_host, mut hosts) = self.parse_raw(reader)?;
+        let (global_host, hosts) = self.parse_raw(reader, &mut Includes::default())?;
+
+        Ok(apply_global_host(&global_host, hosts))
+    }
 
-        if !global_host.is_empty() {
-            for host in &mut hosts {
-                host.extend_if_not_contained(&global_host);
+    /// Reads the annotations and the description of the host block a comment line is in.
+    fn parse_host_comment(&self, host: &mut Host, line: &str) {
+        if let Some(command) = parse_command_annotation(line) {
+            host.set_command(command.to_string());
+        } else if let Some(tags) = parse_tags_annotation(line, &self.tags_annotation_prefix) {
+            host.add_tags(tags);
+        } else if host.get_description().is_none() && host.is_empty() {
+            // Comments after the first entry may introduce the next blocks
+            if let Some(description) = parse_comment(line) {
+                host.set_description(description.to_string());
             }
         }
+    }
 
-        Ok(hosts)
+    /// Files of an `Include` line, none for a missing file when they are ignored.
+    fn resolve_include(
+        &self,
+        include_value: &str,
+        location: &Location<'_>,
+    ) -> Result<Vec<PathBuf>, InvalidIncludeErrorDetails> {
+        match include_paths(include_value) {
+            Err(InvalidIncludeErrorDetails::NotFound(missing)) if self.ignore_missing_includes => {
+                log::warn!(
+                    "{location}: skipping the missing include `{}`",
+                    missing.display()
+                );
+                Ok(Vec::new())
+            }
+            paths => paths,
+        }
     }
 
-    fn parse_raw(&self, reader: &mut impl BufRead) -> Result<(Host, Vec<Host>), ParseError> {
+    fn parse_raw(
+        &self,
+        reader: &mut impl BufRead,
+        includes: &mut Includes,
+    ) -> Result<(Host, Vec<Host>), ParseError> {
         let mut global_host = Host::new(Vec::new());
         let mut is_in_host_block = false;
-        let mut hosts = Vec::new();
+        let mut hosts: Vec<Host> = Vec::new();
+
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content)?;
+
+        // Comments written in another encoding than UTF-8 don't prevent parsing the file
+        let content = String::from_utf8_lossy(&content);
+
+        // Configurations authored on Windows (CRLF) or classic Mac OS (CR)
+        let content = content.replace("\r\n", "\n").replace('\r', "\n");
 
-        let mut line = String::new();
-        while reader.read_line(&mut line)? > 0 {
-            line = line.trim().to_string();
-            if line.is_empty() || line.starts_with('#') {
-                line.clear();
+        // The file being parsed stays the last one of the stack while parsing its lines
+        let path = includes.stack.last().cloned();
+
+        for (line_number, line) in join_continuations(&content) {
+            let line = line.trim().to_string();
+            if line.is_empty() {
                 continue;
             }
 
-            let entry = parse_line(&line)?;
-            line.clear();
+            if line.starts_with('#') {
+                if let Some(host) = hosts.last_mut().filter(|_| is_in_host_block) {
+                    self.parse_host_comment(host, &line);
+                }
 
-            match entry.0 {
-                EntryType::Unknown(_) => {
-                    if !self.ignore_unknown_entries {
-                        return Err(UnknownEntryError {
-                            line,
-                            entry: entry.0.to_string(),
-                        }
-                        .into());
+                continue;
+            }
+
+            let invalid_include = |details| InvalidIncludeError {
+                path: path.clone(),
+                line_number,
+                line: line.clone(),
+                details,
+            };
+
+            let Some(entry) = parse_line(&line) else {
+                return Err(UnparseableLineError {
+                    path: path.clone(),
+                    line_number,
+                    line,
+                }
+                .into());
+            };
+
+            match &entry.0 {
+                EntryType::Unknown(key) if !self.ignore_unknown_entries => {
+                    return Err(UnknownEntryError {
+                        path: path.clone(),
+                        line_number,
+                        line,
+                        entry: key.clone(),
                     }
+                    .into());
                 }
                 EntryType::Host => {
-                    let patterns = parse_patterns(&entry.1);
-                    hosts.push(Host::new(patterns));
+                    hosts.push(host_block(&entry.1, path.as_ref(), line_number));
                     is_in_host_block = true;
 
                     continue;
                 }
                 EntryType::Include => {
-                    let mut include_path = shellexpand::tilde(&entry.1).to_string();
-
-                    if !include_path.starts_with('/') {
-                        let ssh_config_directory = shellexpand::tilde("~/.ssh").to_string();
-                        include_path = format!("{ssh_config_directory}/{include_path}");
-                    }
-
-                    let paths = match glob(&include_path) {
-                        Ok(paths) => paths,
-                        Err(e) => {
-                            return Err(InvalidIncludeError {
-                                line,
-                                details: InvalidIncludeErrorDetails::Pattern(e),
-                            }
-                            .into())
+                    let paths = self
+                        .resolve_include(&entry.1, &Location(path.as_ref(), line_number))
+                        .map_err(invalid_include)?;
+                    for path in paths {
+                        self.check_include(&path, &includes.stack)
+                            .map_err(invalid_include)?;
+                        if !is_in_host_block && !includes.mark_parsed(&path) {
+                            continue;
                         }
-                    };
+                        log::debug!("Including `{}`", path.display());
 
-                    for path in paths {
-                        let path = match path {
-                            Ok(path) => path,
-                            Err(e) => {
-                                return Err(InvalidIncludeError {
-                                    line,
-                                    details: InvalidIncludeErrorDetails::Glob(e),
-                                }
-                                .into())
+                        let mut file = BufReader::new(File::open(&path)?);
+                        if let Ok(mut included_paths) = self.included_paths.lock() {
+                            if !included_paths.contains(&path) {
+                                included_paths.push(path.clone());
                             }
-                        };
-
-                        let mut file = BufReader::new(File::open(path)?);
-                        let (included_global_host, included_hosts) = self.parse_raw(&mut file)?;
+                        }
+                        includes.stack.push(path);
+                        let parsed = self.parse_raw(&mut file, includes);
+                        includes.stack.pop();
+                        let (included_global_host, included_hosts) = parsed?;
 
                         if is_in_host_block {
                             // Can't include hosts inside a host block
                             if !included_hosts.is_empty() {
-                                return Err(InvalidIncludeError {
-                                    line,
-                                    details: InvalidIncludeErrorDetails::HostsInsideHostBlock,
-                                }
+                                return Err(invalid_include(
+                                    InvalidIncludeErrorDetails::HostsInsideHostBlock,
+                                )
                                 .into());
                             }
 
+                            // Like ssh, the first value obtained for an option is used
                             hosts
                                 .last_mut()
                                 .unwrap()
-                                .extend_entries(&included_global_host);
+                                .extend_if_not_contained(&included_global_host);
                         } else {
                             if !included_global_host.is_empty() {
                                 global_host.extend_entries(&included_global_host);
@@ -161,14 +294,186 @@ impl Parser {
 
         Ok((global_host, hosts))
     }
+
+    /// Checks that the canonicalized `path` can be included by the last file of the
+    /// `include_stack`, the files being parsed.
+    fn check_include(
+        &self,
+        path: &Path,
+        include_stack: &[PathBuf],
+    ) -> Result<(), InvalidIncludeErrorDetails> {
+        if include_stack.iter().any(|included| included == path) {
+            return Err(InvalidIncludeErrorDetails::Cycle(path.to_path_buf()));
+        }
+
+        let is_allowed = self.include_allow_roots.is_empty()
+            || self
+                .include_allow_roots
+                .iter()
+                .any(|root| path.starts_with(root));
+        if !is_allowed {
+            return Err(InvalidIncludeErrorDetails::OutsideAllowedRoots(
+                path.to_path_buf(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Canonicalized paths of the included files, while parsing.
+#[derive(Default)]
+struct Includes {
+    /// Files being parsed, the last one being the current file.
+    stack: Vec<PathBuf>,
+    /// Files included outside of the host blocks, which are only parsed once. Each host block
+    /// including a file gets its options, so those are parsed again.
+    parsed: Vec<PathBuf>,
+}
+
+impl Includes {
+    /// Returns `false` if the file was already included outside of the host blocks.
+    fn mark_parsed(&mut self, path: &Path) -> bool {
+        if self.parsed.iter().any(|parsed| parsed == path) {
+            log::debug!("Skipping `{}`, it is already included", path.display());
+            return false;
+        }
+
+        self.parsed.push(path.to_path_buf());
+        true
+    }
+}
+
+/// Resolves the files matched by an `Include` entry, in glob order, canonicalized.
+///
+/// `~` and environment variables (`$VAR` or `${VAR}`) are expanded in the path. A path resolving
+/// to a single directory includes the files of the directory, like `dir/*` would.
+///
+/// The matched directories are left out, so symlinks to directories can't expand the include
+/// again, and so are the paths resolving to a file matched before, e.g. a symlink next to
+/// its target.
+fn include_paths(include_value: &str) -> Result<Vec<PathBuf>, InvalidIncludeErrorDetails> {
+    let mut include_path = shellexpand::full(include_value)
+        .map_err(InvalidIncludeErrorDetails::Variable)?
+        .to_string();
+
+    if !include_path.starts_with('/') {
+        let ssh_config_directory = shellexpand::tilde("~/.ssh").to_string();
+        include_path = format!("{ssh_config_directory}/{include_path}");
+    }
+
+    let mut paths = glob_paths(&include_path)?;
+
+    match paths.as_slice() {
+        [] if !include_path.contains(['*', '?', '[']) => {
+            return Err(InvalidIncludeErrorDetails::NotFound(PathBuf::from(
+                include_path,
+            )));
+        }
+        [directory] if directory.is_dir() => {
+            let directory = glob::Pattern::escape(&directory.to_string_lossy());
+            paths = glob_paths(&format!("{directory}/*"))?;
+        }
+        _ => {}
+    }
+
+    let mut resolved_paths: Vec<PathBuf> = Vec::new();
+    for path in paths.into_iter().filter(|path| !path.is_dir()) {
+        let resolved_path = std::fs::canonicalize(&path)
+            .map_err(|e| InvalidIncludeErrorDetails::Unresolvable(path.clone(), e))?;
+
+        if resolved_paths.contains(&resolved_path) {
+            log::debug!(
+                "Skipping `{}`, `{}` is already included",
+                path.display(),
+                resolved_path.display()
+            );
+            continue;
+        }
+        resolved_paths.push(resolved_path);
+    }
+
+    Ok(resolved_paths)
+}
+
+fn glob_paths(pattern: &str) -> Result<Vec<PathBuf>, InvalidIncludeErrorDetails> {
+    glob(pattern)
+        .map_err(InvalidIncludeErrorDetails::Pattern)?
+        .map(|path| path.map_err(InvalidIncludeErrorDetails::Glob))
+        .collect()
+}
+
+/// Joins the lines ending with an unescaped backslash with the next one, the backslash and the
+/// indentation of the next line being replaced by a space.
+///
+/// ssh itself has no line continuations and never continues a comment, so the comment lines
+/// are kept as they are, neither continuing onto the next line nor joined to the previous one.
+/// Returns the joined lines with the number of their first line.
+fn join_continuations(content: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    let mut is_continued = false;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with('#') {
+            lines.push((line_index + 1, line.to_string()));
+            is_continued = false;
+            continue;
+        }
+
+        let trailing_backslashes = line.len() - line.trim_end_matches('\\').len();
+        let continues = trailing_backslashes % 2 == 1;
+        let line = if continues {
+            line[..line.len() - 1].trim_end()
+        } else {
+            line
+        };
+
+        match lines.last_mut() {
+            Some((_, joined)) if is_continued => {
+                joined.push(' ');
+                joined.push_str(line.trim_start());
+            }
+            _ => lines.push((line_index + 1, line.to_string())),
+        }
+        is_continued = continues;
+    }
+
+    lines
+}
+
+/// Starts the host block of a `Host` line, its trailing comment being the description.
+fn host_block(value: &str, path: Option<&PathBuf>, line_number: usize) -> Host {
+    let (patterns, comment) = split_trailing_comment(value);
+    let mut host = Host::new(parse_patterns(patterns)).with_source_path(path.cloned());
+    if let Some(description) = comment.and_then(parse_comment) {
+        host.set_description(description.to_string());
+    }
+
+    log::debug!(
+        "{}: host `{}`",
+        Location(path, line_number),
+        host.get_patterns().join(" ")
+    );
+    host
 }
 
-fn parse_line(line: &str) -> Result<Entry, ParseError> {
+fn apply_global_host(global_host: &Host, mut hosts: Vec<Host>) -> Vec<Host> {
+    if !global_host.is_empty() {
+        for host in &mut hosts {
+            host.extend_if_not_contained(global_host);
+        }
+    }
+
+    hosts
+}
+
+/// Splits the line into its entry, `None` if it has no value.
+fn parse_line(line: &str) -> Option<Entry> {
     let (mut key, mut value) = line
         .trim()
         .split_once([' ', '\t', '='])
-        .map(|(k, v)| (k.trim_end(), v.trim_start()))
-        .ok_or(ParseError::UnparseableLine(line.to_string()))?;
+        .map(|(k, v)| (k.trim_end(), v.trim_start()))?;
 
     // Format can be key=value with whitespaces around the equal sign, strip the equal sign and whitespaces
     if key.ends_with('=') {
@@ -178,10 +483,114 @@ fn parse_line(line: &str) -> Result<Entry, ParseError> {
         value = value.trim_start_matches('=').trim_start();
     }
 
-    Ok((
-        EntryType::from_str(key).unwrap_or(EntryType::Unknown(key.to_string())),
-        value.to_string(),
-    ))
+    let entry_type = EntryType::from_str(key).unwrap_or(EntryType::Unknown(key.to_string()));
+    let value = match entry_type {
+        // Patterns are quoted one by one, see `parse_patterns`
+        EntryType::Host | EntryType::Match => value.to_string(),
+        _ => unquote_value(value),
+    };
+
+    Some((entry_type, value))
+}
+
+/// Strips the double quotes surrounding the whole value, unescaping the `\"` and `\\` inside them.
+///
+/// Values that aren't quoted as a whole, like `ssh -W "%h:%p" bastion`, are kept as written.
+fn unquote_value(value: &str) -> String {
+    let Some(quoted) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return value.to_string();
+    };
+
+    let mut unquoted = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('"' | '\\')) => unquoted.push(c),
+                Some(c) => {
+                    unquoted.push('\\');
+                    unquoted.push(c);
+                }
+                // The last quote is escaped
+                None => return value.to_string(),
+            },
+            // The first quote is closed before the end
+            '"' => return value.to_string(),
+            c => unquoted.push(c),
+        }
+    }
+
+    unquoted
+}
+
+/// Parses the tags of a `#sshs tag1 tag2` or `# <prefix> tag1 tag2` annotation comment.
+///
+/// Annotations are regular comments to ssh, they tag the host block they are written in.
+fn parse_tags_annotation<'a>(
+    line: &'a str,
+    prefix: &str,
+) -> Option<impl Iterator<Item = String> + 'a> {
+    let is_word_end = |tags: &str| tags.is_empty() || tags.starts_with(char::is_whitespace);
+
+    let tags = match line.strip_prefix("#sshs") {
+        Some(tags) if is_word_end(tags) => tags,
+        _ => {
+            let tags = line.strip_prefix('#')?.trim_start().strip_prefix(prefix)?;
+            // A prefix like `tags` doesn't annotate `# tagsfoo`
+            if prefix.ends_with(char::is_alphanumeric) && !is_word_end(tags) {
+                return None;
+            }
+            tags
+        }
+    };
+
+    Some(
+        tags.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string),
+    )
+}
+
+/// Parses the command template of a `# sshs-command: <template>` annotation comment.
+///
+/// The command replaces the command template for the host block it is written in and the
+/// hosts this block's patterns apply to.
+fn parse_command_annotation(line: &str) -> Option<&str> {
+    let command = line
+        .strip_prefix('#')?
+        .trim_start()
+        .strip_prefix(COMMAND_ANNOTATION_PREFIX)?
+        .trim();
+
+    (!command.is_empty()).then_some(command)
+}
+
+/// Splits the `# comment` ending the patterns of a `Host` line from them, ssh ignoring it.
+fn split_trailing_comment(entry_value: &str) -> (&str, Option<&str>) {
+    let mut in_double_quotes = false;
+    let mut is_word_start = true;
+
+    for (index, c) in entry_value.char_indices() {
+        match c {
+            '"' => in_double_quotes = !in_double_quotes,
+            '#' if is_word_start && !in_double_quotes => {
+                return (entry_value[..index].trim_end(), Some(&entry_value[index..]));
+            }
+            _ => {}
+        }
+        is_word_start = c.is_whitespace();
+    }
+
+    (entry_value, None)
+}
+
+/// Text of a comment line, `None` if there is none, e.g. in a `#` separator line.
+fn parse_comment(line: &str) -> Option<&str> {
+    let comment = line.trim_start_matches('#').trim();
+    (!comment.is_empty()).then_some(comment)
 }
 
 fn parse_patterns(entry_value: &str) -> Vec<String> {
@@ -218,3 +627,433 @@ fn parse_patterns(entry_value: &str) -> Vec<String> {
 
     patterns
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_entry() {
+        let config = "Host example\n  Hostnme example.com\n";
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts.len(), 1);
+
+        let err = Parser::new()
+            .ignore_unknown_entries(false)
+            .parse(&mut config.as_bytes())
+            .unwrap_err();
+
+        let ParseError::UnknownEntry(err) = err else {
+            panic!("expected an unknown entry error, got {err:?}");
+        };
+        assert_eq!(err.path, None);
+        assert_eq!(err.line_number, 2);
+        assert_eq!(err.line, "Hostnme example.com");
+        assert_eq!(err.entry, "Hostnme");
+    }
+
+    #[test]
+    fn test_quoted_values() {
+        let config = r#"
+Host "my server" other
+  User "my user"
+  Hostname="example.com"
+  ProxyCommand "ssh -W %h:%p \"jump host\""
+  IdentityFile "~/.ssh/id" "~/.ssh/other"
+  LocalCommand "escaped end\"
+  RemoteCommand "
+  CertificateFile "C:\\keys\\"
+"#;
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts[0].get_patterns(), &["my server", "other"]);
+
+        let host = &hosts[0];
+        assert_eq!(host.get(&EntryType::User).unwrap(), "my user");
+        assert_eq!(host.get(&EntryType::Hostname).unwrap(), "example.com");
+        assert_eq!(
+            host.get(&EntryType::ProxyCommand).unwrap(),
+            r#"ssh -W %h:%p "jump host""#
+        );
+        assert_eq!(
+            host.get(&EntryType::IdentityFile).unwrap(),
+            r#""~/.ssh/id" "~/.ssh/other""#
+        );
+        assert_eq!(
+            host.get(&EntryType::LocalCommand).unwrap(),
+            r#""escaped end\""#
+        );
+        assert_eq!(host.get(&EntryType::RemoteCommand).unwrap(), r#"""#);
+        assert_eq!(host.get(&EntryType::CertificateFile).unwrap(), r"C:\keys\");
+    }
+
+    #[test]
+    fn test_line_endings() {
+        for line_ending in ["\r\n", "\r"] {
+            let config = ["Host example", "  User root", "  Port = 22", ""].join(line_ending);
+
+            let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+
+            assert_eq!(hosts.len(), 1);
+            assert_eq!(hosts[0].get_patterns(), &["example"]);
+            assert_eq!(hosts[0].get(&EntryType::User).unwrap(), "root");
+            assert_eq!(hosts[0].get(&EntryType::Port).unwrap(), "22");
+        }
+    }
+
+    #[test]
+    fn test_line_continuations() {
+        let config = r"
+Host example
+  ProxyCommand ssh \
+    -W %h:%p \
+    bastion
+  # LocalCommand echo \
+  User root
+  RemoteCommand echo \\
+  Hostnme \
+    example.com
+";
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        let host = &hosts[0];
+        assert_eq!(
+            host.get(&EntryType::ProxyCommand).unwrap(),
+            "ssh -W %h:%p bastion"
+        );
+        assert_eq!(host.get(&EntryType::User).unwrap(), "root");
+        assert_eq!(host.get(&EntryType::RemoteCommand).unwrap(), r"echo \\");
+
+        let err = Parser::new()
+            .ignore_unknown_entries(false)
+            .parse(&mut config.as_bytes())
+            .unwrap_err();
+        let ParseError::UnknownEntry(err) = err else {
+            panic!("expected an unknown entry error, got {err:?}");
+        };
+        assert_eq!(err.line_number, 9);
+        assert_eq!(err.line, "Hostnme example.com");
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut config = b"Host example\n  # Serveur de d".to_vec();
+        config.extend([0xe9]); // `é` in Latin-1
+        config.extend(b"veloppement\n  User root\n");
+
+        let hosts = Parser::new()
+            .ignore_unknown_entries(false)
+            .parse(&mut config.as_slice())
+            .unwrap();
+        assert_eq!(hosts[0].get(&EntryType::User).unwrap(), "root");
+    }
+
+    #[test]
+    fn test_global_entries_are_inherited() {
+        let config = "User admin\nPort 2222\n\nHost example\n  Port 22\n";
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert!(hosts[0].is_inherited(&EntryType::User));
+        assert!(!hosts[0].is_inherited(&EntryType::Port));
+        assert_eq!(hosts[0].get(&EntryType::Port).unwrap(), "22");
+    }
+
+    #[test]
+    fn test_merged_entries_stay_inherited() {
+        use crate::ssh_config::HostVecExt;
+
+        let config = "User g\n\nHost a\n  Port 22\n\nHost b\n  Port 22\n";
+
+        let hosts = Parser::new()
+            .parse(&mut config.as_bytes())
+            .unwrap()
+            .apply_patterns()
+            .merge_same_hosts();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].get_patterns(), &["a", "b"]);
+        assert!(hosts[0].is_inherited(&EntryType::User));
+        assert!(!hosts[0].is_inherited(&EntryType::Port));
+    }
+
+    #[test]
+    fn test_command_annotation() {
+        use crate::ssh_config::HostVecExt;
+
+        let config = "# sshs-command: ignored\nHost slow\n  #sshs-command: mosh {{{name}}}\n\nHost *.lan\n  # sshs-command:\n  # sshs-command: ssh -A {{{name}}}\n\nHost db.lan\n";
+
+        let hosts = Parser::new()
+            .parse(&mut config.as_bytes())
+            .unwrap()
+            .apply_patterns();
+        assert_eq!(hosts[0].get_command(), Some("mosh {{{name}}}"));
+        assert_eq!(hosts[1].get_patterns(), &["db.lan"]);
+        assert_eq!(hosts[1].get_command(), Some("ssh -A {{{name}}}"));
+        assert!(hosts[0].get_tags().is_empty());
+    }
+
+    #[test]
+    fn test_description() {
+        let config = r##"
+Host web "my server" # Production web server
+  #sshs prod
+  # Ignored, the Host line has one
+  User root
+
+Host db
+  #
+  # Main database
+  Port 5432
+  # Caches
+Host cache
+  User redis
+  # Not a description
+Host "#literal"
+"##;
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts[0].get_patterns(), &["web", "my server"]);
+        assert_eq!(hosts[0].get_description(), Some("Production web server"));
+        assert_eq!(hosts[0].get_tags(), &["prod"]);
+        assert_eq!(hosts[1].get_description(), Some("Main database"));
+        assert_eq!(hosts[2].get_description(), None);
+        assert_eq!(hosts[3].get_patterns(), &["#literal"]);
+        assert_eq!(hosts[3].get_description(), None);
+    }
+
+    #[test]
+    fn test_tags_annotation() {
+        let config = "#sshs ignored\nHost dashboard\n  #sshs web, grafana\n  # sshs not-a-tag\n  #sshsweb\n  Hostname dashboard.example.com\n";
+
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].get_tags(), &["web", "grafana"]);
+
+        let config = "Host db\n  # @tags: prod db\n  #@tags: critical\n  # tags: ignored\n";
+        let hosts = Parser::new().parse(&mut config.as_bytes()).unwrap();
+        assert_eq!(hosts[0].get_tags(), &["prod", "db", "critical"]);
+
+        let config = "Host db\n  # tags prod\n  # @tags: ignored\n  # tagsfoo\n  #sshs db\n";
+        let hosts = Parser::new()
+            .tags_annotation_prefix("tags")
+            .parse(&mut config.as_bytes())
+            .unwrap();
+        assert_eq!(hosts[0].get_tags(), &["prod", "db"]);
+    }
+
+    #[test]
+    fn test_include_environment_variable() {
+        let directory = std::env::temp_dir().join("sshs-test-include-environment-variable");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("hosts"), "Host included\n").unwrap();
+
+        std::env::set_var("SSHS_TEST_CONFIG_DIR", &directory);
+        let parse = |config: &str| Parser::new().parse(&mut config.as_bytes());
+        let braced = parse("Include ${SSHS_TEST_CONFIG_DIR}/hosts\n");
+        let unbraced = parse("Include $SSHS_TEST_CONFIG_DIR/hosts\n");
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        for hosts in [braced, unbraced] {
+            let hosts = hosts.unwrap();
+            assert_eq!(hosts.len(), 1);
+            assert_eq!(hosts[0].get_patterns(), &["included"]);
+        }
+
+        let config = "Include $SSHS_TEST_UNSET_VARIABLE/hosts\n";
+        let err = Parser::new().parse(&mut config.as_bytes()).unwrap_err();
+
+        let ParseError::InvalidInclude(err) = err else {
+            panic!("expected an invalid include error, got {err:?}");
+        };
+        assert!(matches!(
+            err.details,
+            InvalidIncludeErrorDetails::Variable(e) if e.var_name == "SSHS_TEST_UNSET_VARIABLE"
+        ));
+    }
+
+    #[test]
+    fn test_include_allow_roots() {
+        let directory = std::env::temp_dir().join("sshs-test-include-allow-roots");
+        let allowed = directory.join("allowed");
+        std::fs::create_dir_all(&allowed).unwrap();
+        std::fs::write(allowed.join("hosts"), "Host allowed\n").unwrap();
+        std::fs::write(directory.join("hosts"), "Host outside\n").unwrap();
+
+        let parse = |include: &str| {
+            let config = format!("Include {}\n", directory.join(include).display());
+            Parser::new()
+                .include_allow_roots(std::slice::from_ref(&allowed))
+                .parse(&mut config.as_bytes())
+        };
+        let inside = parse("allowed/hosts");
+        let outside = parse("hosts");
+        let traversal = parse("allowed/../hosts");
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(inside.unwrap()[0].get_patterns(), &["allowed"]);
+        for result in [outside, traversal] {
+            let Err(ParseError::InvalidInclude(err)) = result else {
+                panic!("expected an invalid include error, got {result:?}");
+            };
+            assert!(matches!(
+                err.details,
+                InvalidIncludeErrorDetails::OutsideAllowedRoots(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_include_inside_host() {
+        let directory = std::env::temp_dir().join("sshs-test-include-inside-host");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let included = directory.join("options");
+        std::fs::write(&included, "Port 22\nUser included\n").unwrap();
+
+        let config = format!(
+            "Host example\n  Port 2222\n  Include {}\n  Hostname example.com\n",
+            included.display()
+        );
+        let parser = Parser::new();
+        let hosts = parser.parse(&mut config.as_bytes());
+        let included = std::fs::canonicalize(&included).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(parser.included_paths(), [included]);
+
+        let hosts = hosts.unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].get(&EntryType::Port).unwrap(), "2222");
+        assert_eq!(hosts[0].get(&EntryType::User).unwrap(), "included");
+        assert_eq!(hosts[0].get(&EntryType::Hostname).unwrap(), "example.com");
+    }
+
+    #[test]
+    fn test_include_directory() {
+        let directory = std::env::temp_dir().join("sshs-test-include-directory");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("config.d").join("nested")).unwrap();
+        std::fs::write(directory.join("config.d").join("a"), "Host a\n").unwrap();
+        std::fs::write(directory.join("config.d").join("b"), "Host b\n").unwrap();
+
+        let parse = |include: String| {
+            let config = format!("Include {include}\n");
+            Parser::new().parse(&mut config.as_bytes()).map(|hosts| {
+                hosts
+                    .iter()
+                    .map(|host| host.get_patterns()[0].clone())
+                    .collect::<Vec<_>>()
+            })
+        };
+        let directory_include = parse(directory.join("config.d").display().to_string());
+        let glob_include = parse(format!("{}/config.*", directory.display()));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(directory_include.unwrap(), ["a", "b"]);
+        assert_eq!(glob_include.unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_include_missing() {
+        let directory = std::env::temp_dir().join("sshs-test-include-missing");
+
+        let config = format!("Include {}/*\n", directory.display());
+        let hosts = Parser::new().parse(&mut config.as_bytes());
+        assert!(hosts.unwrap().is_empty());
+
+        let missing = directory.join("missing");
+        let config = format!("Include {}\nHost web\n", missing.display());
+        let hosts = Parser::new().parse(&mut config.as_bytes());
+        assert_eq!(*hosts.unwrap()[0].get_patterns(), ["web"]);
+
+        let err = Parser::new()
+            .ignore_missing_includes(false)
+            .parse(&mut config.as_bytes())
+            .unwrap_err();
+
+        let ParseError::InvalidInclude(err) = err else {
+            panic!("expected an invalid include error, got {err:?}");
+        };
+        assert!(
+            matches!(err.details, InvalidIncludeErrorDetails::NotFound(path) if path == missing)
+        );
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let directory = std::env::temp_dir().join("sshs-test-include-cycle");
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let a = directory.join("a");
+        let b = directory.join("b");
+        std::fs::write(&a, format!("Include {}\n", b.display())).unwrap();
+        std::fs::write(&b, format!("Host b\n\nInclude {}\n", a.display())).unwrap();
+
+        let err = Parser::new().parse_file(&a).unwrap_err();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let ParseError::InvalidInclude(err) = err else {
+            panic!("expected an invalid include error, got {err:?}");
+        };
+        assert_eq!(err.line, format!("Include {}", a.display()));
+        assert!(
+            matches!(err.details, InvalidIncludeErrorDetails::Cycle(path) if path.ends_with("a"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_include_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let directory = std::env::temp_dir().join("sshs-test-include-symlinks");
+        let config_directory = directory.join("config.d");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&config_directory).unwrap();
+        std::fs::write(config_directory.join("a"), "Host a\n").unwrap();
+        std::fs::write(directory.join("options"), "User admin\n").unwrap();
+        symlink(config_directory.join("a"), config_directory.join("b")).unwrap();
+        symlink(&config_directory, config_directory.join("loop")).unwrap();
+        symlink(&directory, config_directory.join("parent")).unwrap();
+
+        let config = format!("Include {0}\nInclude {0}/*\n", config_directory.display());
+        let hosts = Parser::new().parse(&mut config.as_bytes());
+
+        let config = format!(
+            "Host web\n  Include {0}/options\nHost db\n  Include {0}/options\n",
+            directory.display()
+        );
+        let host_block_hosts = Parser::new().parse(&mut config.as_bytes());
+
+        symlink(directory.join("self"), directory.join("self")).unwrap();
+        let config = format!("Include {}/self\n", directory.display());
+        let err = Parser::new().parse(&mut config.as_bytes()).unwrap_err();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let patterns = hosts
+            .unwrap()
+            .iter()
+            .map(|host| host.get_patterns()[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(patterns, ["a"]);
+
+        // Each host block including a file gets its options
+        for host in host_block_hosts.unwrap() {
+            assert_eq!(
+                host.get(&EntryType::User).as_deref(),
+                Some("admin"),
+                "{:?}",
+                host.get_patterns()
+            );
+        }
+
+        let ParseError::InvalidInclude(err) = err else {
+            panic!("expected an invalid include error, got {err:?}");
+        };
+        assert!(matches!(
+            err.details,
+            InvalidIncludeErrorDetails::Unresolvable(path, _) if path.ends_with("self")
+        ));
+    }
+}
@@ -1,19 +1,46 @@
+use std::fmt;
+use std::path::PathBuf;
+
 #[derive(Debug)]
 pub struct UnknownEntryError {
+    /// File containing the entry, `None` when parsing from a reader.
+    pub path: Option<PathBuf>,
+    pub line_number: usize,
     pub line: String,
     pub entry: String,
 }
 
+#[derive(Debug)]
+pub struct UnparseableLineError {
+    /// File containing the line, `None` when parsing from a reader.
+    pub path: Option<PathBuf>,
+    pub line_number: usize,
+    pub line: String,
+}
+
 #[derive(Debug)]
 pub enum InvalidIncludeErrorDetails {
     Pattern(glob::PatternError),
     Glob(glob::GlobError),
+    Variable(shellexpand::LookupError<std::env::VarError>),
     Io(std::io::Error),
     HostsInsideHostBlock,
+    /// The included file is already being parsed.
+    Cycle(PathBuf),
+    /// The included path isn't a pattern and doesn't exist.
+    NotFound(PathBuf),
+    /// The included path can't be canonicalized, e.g. a symlink loop or a dangling symlink.
+    Unresolvable(PathBuf, std::io::Error),
+    /// The included file isn't inside any of the allowed roots, see
+    /// [`Parser::include_allow_roots`](super::Parser::include_allow_roots).
+    OutsideAllowedRoots(PathBuf),
 }
 
 #[derive(Debug)]
 pub struct InvalidIncludeError {
+    /// File containing the include, `None` when parsing from a reader.
+    pub path: Option<PathBuf>,
+    pub line_number: usize,
     pub line: String,
     pub details: InvalidIncludeErrorDetails,
 }
@@ -21,7 +48,7 @@ pub struct InvalidIncludeError {
 #[derive(Debug)]
 pub enum ParseError {
     Io(std::io::Error),
-    UnparseableLine(String),
+    UnparseableLine(UnparseableLineError),
     UnknownEntry(UnknownEntryError),
     InvalidInclude(InvalidIncludeError),
 }
@@ -32,6 +59,12 @@ impl From<std::io::Error> for ParseError {
     }
 }
 
+impl From<UnparseableLineError> for ParseError {
+    fn from(e: UnparseableLineError) -> Self {
+        ParseError::UnparseableLine(e)
+    }
+}
+
 impl From<UnknownEntryError> for ParseError {
     fn from(e: UnknownEntryError) -> Self {
         ParseError::UnknownEntry(e)
@@ -43,3 +76,88 @@ impl From<InvalidIncludeError> for ParseError {
         ParseError::InvalidInclude(e)
     }
 }
+
+/// Displays `path:line_number`, or only the line number without a path.
+pub(crate) struct Location<'a>(pub Option<&'a PathBuf>, pub usize);
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_location(f, self.0, self.1)
+    }
+}
+
+/// Writes `path:line_number`, or only the line number without a path.
+fn write_location(
+    f: &mut fmt::Formatter<'_>,
+    path: Option<&PathBuf>,
+    line_number: usize,
+) -> fmt::Result {
+    match path {
+        Some(path) => write!(f, "{}:{line_number}", path.display()),
+        None => write!(f, "line {line_number}"),
+    }
+}
+
+impl fmt::Display for UnparseableLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_location(f, self.path.as_ref(), self.line_number)?;
+        write!(f, ": unparseable line `{}`", self.line)
+    }
+}
+
+impl fmt::Display for UnknownEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_location(f, self.path.as_ref(), self.line_number)?;
+        write!(f, ": unknown entry `{}` in `{}`", self.entry, self.line)
+    }
+}
+
+impl fmt::Display for InvalidIncludeErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidIncludeErrorDetails::Pattern(e) => write!(f, "{e}"),
+            InvalidIncludeErrorDetails::Glob(e) => write!(f, "{e}"),
+            InvalidIncludeErrorDetails::Variable(e) => write!(f, "{e}"),
+            InvalidIncludeErrorDetails::Io(e) => write!(f, "{e}"),
+            InvalidIncludeErrorDetails::HostsInsideHostBlock => {
+                write!(f, "hosts can't be included inside a host block")
+            }
+            InvalidIncludeErrorDetails::Cycle(path) => {
+                write!(f, "`{}` is already being included", path.display())
+            }
+            InvalidIncludeErrorDetails::NotFound(path) => {
+                write!(f, "`{}` doesn't exist", path.display())
+            }
+            InvalidIncludeErrorDetails::Unresolvable(path, e) => {
+                write!(f, "`{}` can't be resolved: {e}", path.display())
+            }
+            InvalidIncludeErrorDetails::OutsideAllowedRoots(path) => {
+                write!(
+                    f,
+                    "`{}` is outside the allowed include roots",
+                    path.display()
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for InvalidIncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_location(f, self.path.as_ref(), self.line_number)?;
+        write!(f, ": invalid include `{}`: {}", self.line, self.details)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{e}"),
+            ParseError::UnparseableLine(e) => write!(f, "{e}"),
+            ParseError::UnknownEntry(e) => write!(f, "{e}"),
+            ParseError::InvalidInclude(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,183 @@
+//! Writes hosts back in the SSH configuration syntax, the [`Parser`](super::Parser) reading
+//! them as they were.
+
+use std::io::{self, Write};
+
+use super::parser::COMMAND_ANNOTATION_PREFIX;
+use super::{EntryType, Host};
+
+/// Writes a `Host` block per host, with its description ending the `Host` line, its entries
+/// sorted by key and its tags and command as `#sshs` and `# sshs-command:` annotations.
+///
+/// Every entry is written in the block, those inherited from the global options or the pattern
+/// hosts included, so the blocks don't depend on each other.
+///
+/// # Errors
+///
+/// Will return `Err` if the writer fails, or before writing anything if a host has a `Match`
+/// entry: the parser keeps it as an entry of the host along with the entries following it, so
+/// the written blocks wouldn't mean the same to ssh.
+pub fn write_hosts(writer: &mut impl Write, hosts: &[Host]) -> io::Result<()> {
+    if let Some(host) = hosts
+        .iter()
+        .find(|host| host.get(&EntryType::Match).is_some())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "`Host {}` has `Match` entries, which can't be exported",
+                host.get_patterns().join(" ")
+            ),
+        ));
+    }
+
+    for (index, host) in hosts.iter().enumerate() {
+        if index > 0 {
+            writeln!(writer)?;
+        }
+
+        let patterns = host
+            .get_patterns()
+            .iter()
+            .map(|pattern| quote_pattern(pattern))
+            .collect::<Vec<_>>();
+        match host.get_description() {
+            Some(description) => writeln!(writer, "Host {} # {description}", patterns.join(" "))?,
+            None => writeln!(writer, "Host {}", patterns.join(" "))?,
+        }
+
+        if !host.get_tags().is_empty() {
+            writeln!(writer, "  #sshs {}", host.get_tags().join(" "))?;
+        }
+        if let Some(command) = host.get_command() {
+            writeln!(writer, "  # {COMMAND_ANNOTATION_PREFIX} {command}")?;
+        }
+
+        let mut entries = host
+            .get_entries()
+            .iter()
+            .map(|(entry_type, value)| (key(entry_type), value))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        for (key, value) in entries {
+            writeln!(writer, "  {key} {}", quote_value(value))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn key(entry_type: &EntryType) -> String {
+    match entry_type {
+        EntryType::Unknown(key) => key.clone(),
+        entry_type => entry_type.to_string(),
+    }
+}
+
+fn quote_pattern(pattern: &str) -> String {
+    if pattern.contains(char::is_whitespace) || pattern.starts_with('#') {
+        format!("\"{pattern}\"")
+    } else {
+        pattern.to_string()
+    }
+}
+
+/// Quotes the values that wouldn't be read as written: the empty ones, those starting or
+/// ending with a space or starting with `=`, and those surrounded by double quotes.
+fn quote_value(value: &str) -> String {
+    let is_quoted = value.len() > 1 && value.starts_with('"') && value.ends_with('"');
+    if !value.is_empty() && value.trim() == value && !value.starts_with('=') && !is_quoted {
+        return value.to_string();
+    }
+
+    // Backslashes first, so that the ones escaping the quotes aren't doubled
+    let escaped = value.replace('\\', r"\\").replace('"', r#"\""#);
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh_config::{HostVecExt, Parser};
+
+    fn resolve(config: &[u8]) -> Vec<Host> {
+        Parser::new()
+            .parse(&mut &config[..])
+            .unwrap()
+            .apply_patterns()
+    }
+
+    #[test]
+    fn test_write_hosts_roundtrip() {
+        let config = br#"
+User admin
+
+Host *.example.com
+  ProxyJump bastion
+
+Host web.example.com "my server"
+  #sshs prod
+  # sshs-command: mosh {{{name}}}
+  Hostname 10.0.0.1
+  LocalCommand "  padded"
+  RemoteCommand "\"quoted\""
+  IdentityFile "C:\keys\"
+  CertificateFile "say \"C:\\keys\\\""
+  Unknown entry
+
+Host db # Main database
+  Port 2222
+"#;
+        let hosts = resolve(config);
+
+        let mut written = Vec::new();
+        write_hosts(&mut written, &hosts).unwrap();
+        let reparsed = resolve(&written);
+
+        let mut rewritten = Vec::new();
+        write_hosts(&mut rewritten, &reparsed).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&rewritten),
+            String::from_utf8_lossy(&written)
+        );
+
+        assert_eq!(reparsed.len(), hosts.len());
+        for (host, reparsed) in hosts.iter().zip(&reparsed) {
+            assert_eq!(reparsed.get_patterns(), host.get_patterns());
+            assert_eq!(reparsed.get_entries(), host.get_entries());
+            assert_eq!(reparsed.get_tags(), host.get_tags());
+            assert_eq!(reparsed.get_command(), host.get_command());
+            assert_eq!(reparsed.get_description(), host.get_description());
+        }
+
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with(
+            "Host web.example.com\n  #sshs prod\n  # sshs-command: mosh {{{name}}}\n"
+        ));
+        assert!(written.contains("Host \"my server\"\n"));
+        assert!(written.contains("  LocalCommand \"  padded\"\n"));
+        assert!(written.contains("  ProxyJump bastion\n"));
+        assert!(written.contains("Host db # Main database\n  Port 2222\n  User admin\n"));
+    }
+
+    #[test]
+    fn test_write_hosts_match() {
+        let config = b"
+Host web
+  Match host foo
+  User matched
+
+Host db
+  User admin
+";
+        let hosts = resolve(config);
+
+        let mut written = Vec::new();
+        let err = write_hosts(&mut written, &hosts).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("`Host web`"), "{err}");
+        assert!(written.is_empty());
+    }
+}
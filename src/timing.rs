@@ -0,0 +1,117 @@
+//! Time spent in the stages of resolving the SSH configuration files, printed on stderr by
+//! `--timing`.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading the files and their includes into hosts
+    Parsing,
+    /// Applying the global options and the pattern hosts to the hosts
+    ApplyingPatterns,
+    /// Merging the hosts having the same entries
+    Merging,
+}
+
+impl Stage {
+    const ALL: [Stage; 3] = [Stage::Parsing, Stage::ApplyingPatterns, Stage::Merging];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Parsing => "parsing",
+            Stage::ApplyingPatterns => "applying patterns",
+            Stage::Merging => "merging",
+        }
+    }
+}
+
+/// Total time spent in each stage, every file included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timings {
+    durations: [Duration; 3],
+}
+
+impl Timings {
+    #[must_use]
+    pub const fn new() -> Timings {
+        Timings {
+            durations: [Duration::ZERO; 3],
+        }
+    }
+
+    pub fn add(&mut self, stage: Stage, duration: Duration) {
+        self.durations[stage as usize] += duration;
+    }
+
+    #[must_use]
+    pub fn get(&self, stage: Stage) -> Duration {
+        self.durations[stage as usize]
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stage in Stage::ALL {
+            writeln!(f, "{}: {:.3?}", stage.name(), self.get(stage))?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts measuring the stages, nothing is measured until then.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `f`, adding the time it takes to the stage when measuring.
+pub fn measure<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let result = f();
+    let duration = start.elapsed();
+
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.add(stage, duration);
+    }
+    result
+}
+
+/// Prints the time spent in each stage on stderr when measuring.
+pub fn report() {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if let Ok(timings) = TIMINGS.lock() {
+        let _ = write!(io::stderr(), "{timings}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let mut timings = Timings::new();
+        timings.add(Stage::Parsing, Duration::from_millis(12));
+        timings.add(Stage::Merging, Duration::from_micros(500));
+        timings.add(Stage::Parsing, Duration::from_millis(3));
+
+        assert_eq!(timings.get(Stage::Parsing), Duration::from_millis(15));
+        assert_eq!(
+            timings.to_string(),
+            "parsing: 15.000ms\napplying patterns: 0.000ns\nmerging: 500.000µs\n"
+        );
+    }
+}
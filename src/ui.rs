@@ -3,51 +3,226 @@ use crossterm::{
     cursor::{Hide, Show},
     event::{
         self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-        KeyModifiers,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use fuzzy_matcher::skim::SkimMatcherV2;
 #[allow(clippy::wildcard_imports)]
 use ratatui::{prelude::*, widgets::*};
 use std::{
     cell::RefCell,
     cmp::{max, min},
+    collections::BTreeSet,
+    fmt::Write as _,
     io,
+    path::{Path, PathBuf},
+    process::ExitStatus,
     rc::Rc,
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+    time::{Duration, Instant},
 };
 use style::palette::tailwind;
 use tui_input::backend::crossterm::EventHandler;
-use tui_input::Input;
-use unicode_width::UnicodeWidthStr;
+use tui_input::{Input, InputRequest};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::keybindings::{Action, Keybindings};
+use crate::{
+    browser,
+    clipboard::Clipboard,
+    column_widths::MinColumnWidths,
+    favorites::Favorites,
+    highlight::Highlight,
+    history::History,
+    labels::Labels,
+    logger,
+    messages::{self, Lang, Messages},
+    notification, presets,
+    searchable::Searchable,
+    ssh, ssh_config,
+    watcher::FileWatcher,
+};
+
+const INFO_SEPARATOR: &str = " | ";
+/// Prefix of the name of the favorite hosts.
+const FAVORITE_MARKER: &str = "★ ";
+/// Prefix of the name of the hosts in the selection.
+const SELECTION_MARKER: &str = "✓ ";
+const ELLIPSIS: &str = "…";
+
+/// Smallest terminal the normal layout is rendered in.
+const MIN_TERMINAL_WIDTH: u16 = 30;
+const MIN_TERMINAL_HEIGHT: u16 = 10;
+
+/// How often the run loop checks for a termination signal while waiting for events.
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
-use crate::{searchable::Searchable, ssh};
+/// How long the `reloaded` message stays in the footer after a `--watch` reload.
+const RELOADED_MESSAGE_DURATION: Duration = Duration::from_secs(2);
 
-const INFO_TEXT: &str = "(Esc) quit | (↑) move up | (↓) move down | (enter) select";
+/// `true` while the terminal is in raw mode on the alternate screen.
+static IS_TERMINAL_SETUP: AtomicBool = AtomicBool::new(false);
+/// Termination signal received while the terminal is set up, `0` if none.
+static PENDING_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Columns are never capped below this width, padding included.
+const MIN_MAX_COLUMN_WIDTH: u16 = 12;
 
 #[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct AppConfig {
     pub config_paths: Vec<String>,
+    /// Directories whose files are all parsed after the `config_paths`.
+    pub include_dirs: Vec<String>,
+    /// Directories the included files must be in, any file can be included when empty.
+    pub include_allow_roots: Vec<PathBuf>,
+    pub strict: bool,
+    pub merge_same_hosts: bool,
+    /// Prefix of the tags annotation comments, the parser's default one if `None`.
+    pub tags_annotation_prefix: Option<String>,
 
     pub search_filter: Option<String>,
-    pub sort_by_name: bool,
+    pub search_fields: Vec<ssh::HostField>,
+    pub sort_by: ssh::SortBy,
+    pub sort_dir: ssh::SortDir,
+    pub require_hostname: bool,
+    /// Stop moving up and down at the first and last hosts instead of wrapping around.
+    pub no_wrap: bool,
     pub show_proxy_command: bool,
+    pub show_source: bool,
+    pub show_description: bool,
+    /// Show the alias matching the search better than the name in the name column.
+    pub show_matched_alias: bool,
+    /// Show `user@destination` in the destination column of the hosts with a user.
+    pub destination_with_user: bool,
+    /// Port shown in the port column of the hosts without one.
+    pub default_port: Option<u16>,
+    /// Show the proxy chain of the selected host next to the table.
+    pub show_proxy_chain: bool,
+    /// Columns of the table in order, the default ones when empty. The columns empty for every
+    /// host matching the search are hidden, except the name.
+    pub columns: Vec<ssh::HostField>,
+    pub min_column_widths: MinColumnWidths,
+    /// Most hosts shown in the table, the best matches when searching.
+    pub max_results: Option<usize>,
+    pub labels: Labels,
+    pub highlight: Highlight,
+    pub keybindings: Keybindings,
+    pub search_history: History,
+    /// Hosts pinned at the top of the table when there is no search.
+    pub favorites: Favorites,
 
     pub command_template: String,
+    /// Named templates to switch to at runtime, after the `command_template`.
+    pub command_template_presets: Vec<(String, String)>,
     pub command_template_on_session_start: Option<String>,
     pub command_template_on_session_end: Option<String>,
+    pub after_command: Option<String>,
+    pub launcher: ssh::Launcher,
+    /// Arguments given to ssh in the commands connecting to the hosts, see `--ssh-args`.
+    pub ssh_args: Vec<String>,
+    pub clipboard: Clipboard,
     pub exit_after_ssh_session_ends: bool,
+    pub dry_run: bool,
+    /// Connect to the only host matching the search on enter, whatever the selection.
+    pub enter_on_unique: bool,
+    /// Run the sessions in the background and notify when they end, see `--detach`.
+    pub detach: bool,
+    /// Reload the hosts when the SSH configuration files change, see `--watch`.
+    pub watch: bool,
+    /// `ConnectTimeout` of every host in seconds, added to the templates running `ssh`.
+    pub connect_timeout: Option<u32>,
+    pub lang: Lang,
+}
+
+impl Default for AppConfig {
+    /// Same as the command line defaults, without configuration files.
+    fn default() -> Self {
+        AppConfig {
+            config_paths: Vec::new(),
+            include_dirs: Vec::new(),
+            include_allow_roots: Vec::new(),
+            strict: false,
+            merge_same_hosts: true,
+            tags_annotation_prefix: None,
+            search_filter: None,
+            search_fields: ssh::DEFAULT_SEARCH_FIELDS.to_vec(),
+            sort_by: ssh::SortBy::default(),
+            sort_dir: ssh::SortDir::default(),
+            require_hostname: false,
+            no_wrap: false,
+            show_proxy_command: false,
+            show_source: false,
+            show_description: false,
+            show_matched_alias: false,
+            destination_with_user: false,
+            default_port: None,
+            show_proxy_chain: false,
+            columns: Vec::new(),
+            min_column_widths: MinColumnWidths::default(),
+            max_results: None,
+            labels: Labels::default(),
+            highlight: Highlight::default(),
+            keybindings: Keybindings::default(),
+            search_history: History::default(),
+            favorites: Favorites::default(),
+            command_template: ssh::DEFAULT_COMMAND_TEMPLATE.to_string(),
+            command_template_presets: Vec::new(),
+            command_template_on_session_start: None,
+            command_template_on_session_end: None,
+            after_command: None,
+            launcher: ssh::Launcher::default(),
+            ssh_args: Vec::new(),
+            clipboard: Clipboard::default(),
+            exit_after_ssh_session_ends: false,
+            dry_run: false,
+            enter_on_unique: false,
+            detach: false,
+            watch: false,
+            connect_timeout: None,
+            lang: Lang::default(),
+        }
+    }
 }
 
 pub struct App {
     config: AppConfig,
 
     search: Input,
+    search_history: History,
+    /// Position in the search history while cycling through it.
+    search_history_index: Option<usize>,
+    /// Search typed before cycling through the history, restored after the most recent search.
+    search_draft: String,
+    favorites: Favorites,
+    /// Names of the hosts to connect to at once, the highlighted host is used when empty.
+    selection: BTreeSet<String>,
+    /// Show all the aliases of every host, not only of the selected one.
+    expand_aliases: bool,
 
     table_state: TableState,
     hosts: Searchable<ssh::Host>,
     table_columns_constraints: Vec<Constraint>,
+    table_area: Rect,
+
+    dry_run_output: Option<Vec<String>>,
+    /// First key of a two keys binding, waiting for the second one.
+    pending_key: Option<char>,
+    /// Message shown in the footer until the next key press.
+    status_message: Option<String>,
+    /// Time the status message is cleared at without a key press, for the ones flashing briefly.
+    status_message_deadline: Option<Instant>,
+    /// Name of the last host a session was started on, with the session exit status.
+    last_session: Option<(String, ExitStatus)>,
+    exit_code: i32,
+    messages: &'static Messages,
+    /// Index of the active template, `0` for the `command_template` then the presets.
+    active_preset: usize,
+    /// Watcher of the SSH configuration files, `None` when not watching them.
+    watcher: Option<FileWatcher>,
+    /// Threads of the sessions started with `--detach`, waited for before exiting.
+    detached_sessions: Vec<std::thread::JoinHandle<()>>,
 
     palette: tailwind::Palette,
 }
@@ -64,70 +239,98 @@ impl App {
     ///
     /// Will return `Err` if the SSH configuration file cannot be parsed.
     pub fn new(config: &AppConfig) -> Result<App> {
-        let mut hosts = Vec::new();
-
-        for path in &config.config_paths {
-            let parsed_hosts = match ssh::parse_config(path) {
-                Ok(hosts) => hosts,
-                Err(err) => {
-                    if path == "/etc/ssh/ssh_config" {
-                        if let ssh::ParseConfigError::Io(io_err) = &err {
-                            // Ignore missing system-wide SSH configuration file
-                            if io_err.kind() == std::io::ErrorKind::NotFound {
-                                continue;
-                            }
-                        }
-                    }
-
-                    anyhow::bail!("Failed to parse SSH configuration file: {err:?}");
-                }
-            };
+        let (hosts, included_paths) = load_hosts(config)?;
+        Ok(App::with_hosts(config, hosts, &included_paths))
+    }
 
-            hosts.extend(parsed_hosts);
-        }
+    /// Same as [`App::new`] with the hosts of the SSH configuration read from `reader` instead
+    /// of the `config_paths` and `include_dirs`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the SSH configuration cannot be parsed.
+    #[cfg(test)]
+    pub fn from_reader(config: &AppConfig, reader: &mut impl io::BufRead) -> Result<App> {
+        let parser = hosts_parser(config);
+        let hosts = ssh::parse_config_reader(reader, &parser)
+            .map_err(|err| anyhow::anyhow!("Failed to parse SSH configuration: {err}"))?;
+
+        Ok(App::with_hosts(
+            config,
+            prepare_hosts(config, hosts),
+            &parser.included_paths(),
+        ))
+    }
 
-        if config.sort_by_name {
-            hosts.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        }
+    fn with_hosts(config: &AppConfig, hosts: Vec<ssh::Host>, included_paths: &[PathBuf]) -> App {
+        let watcher = config
+            .watch
+            .then(|| FileWatcher::new(watched_paths(config, included_paths)));
 
         let search_input = config.search_filter.clone().unwrap_or_default();
         let matcher = SkimMatcherV2::default();
+        let search_fields = config.search_fields.clone();
 
         let mut app = App {
             config: config.clone(),
 
             search: search_input.clone().into(),
+            search_history: config.search_history.clone(),
+            search_history_index: None,
+            search_draft: String::new(),
+            favorites: config.favorites.clone(),
+            selection: BTreeSet::new(),
+            expand_aliases: false,
 
             table_state: TableState::default().with_selected(0),
             table_columns_constraints: Vec::new(),
+            table_area: Rect::default(),
+            dry_run_output: None,
+            pending_key: None,
+            status_message: None,
+            status_message_deadline: None,
+            last_session: None,
+            exit_code: 0,
+            messages: config.lang.messages(),
+            active_preset: 0,
+            watcher,
+            detached_sessions: Vec::new(),
             palette: tailwind::BLUE,
 
-            hosts: Searchable::new(
+            // The best matches come first, the configured sort is kept without a search
+            hosts: Searchable::with_score(
                 hosts,
                 &search_input,
-                move |host: &&ssh::Host, search_value: &str| -> bool {
-                    search_value.is_empty()
-                        || matcher.fuzzy_match(&host.name, search_value).is_some()
-                        || matcher
-                            .fuzzy_match(&host.destination, search_value)
-                            .is_some()
-                        || matcher.fuzzy_match(&host.aliases, search_value).is_some()
+                move |host: &&ssh::Host, search_value: &str| -> Option<i64> {
+                    host.search_score(&matcher, &search_fields, search_value)
                 },
             ),
         };
-        app.calculate_table_columns_constraints();
+        if config.connect_timeout.is_some() {
+            app.config.command_template = ssh::with_connect_timeout(&config.command_template);
+            for (_, template) in &mut app.config.command_template_presets {
+                *template = ssh::with_connect_timeout(template);
+            }
+        }
 
-        Ok(app)
+        app.apply_search();
+
+        app
     }
 
+    /// Runs the user interface and returns the exit code sshs should exit with.
+    ///
     /// # Errors
     ///
     /// Will return `Err` if the terminal cannot be configured.
-    pub fn start(&mut self) -> Result<()> {
+    pub fn start(&mut self) -> Result<i32> {
         let stdout = io::stdout().lock();
         let backend = CrosstermBackend::new(stdout);
         let terminal = Rc::new(RefCell::new(Terminal::new(backend)?));
 
+        #[cfg(unix)]
+        handle_termination_signals()?;
+
         setup_terminal(&terminal)?;
 
         // create app and run it
@@ -139,7 +342,52 @@ impl App {
             println!("{err:?}");
         }
 
-        Ok(())
+        self.wait_for_detached_sessions();
+
+        Ok(self.exit_code)
+    }
+
+    /// Connects to the best match of the search without the user interface, see `--first`,
+    /// and returns the exit code sshs should exit with.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if no host matches the search or the session cannot be started.
+    pub fn connect_first(&mut self) -> Result<i32> {
+        let Some(host) = self.hosts.iter().next().cloned() else {
+            anyhow::bail!("No host matches `{}`", self.search.value());
+        };
+        eprintln!(
+            "{}",
+            messages::format(self.messages.connecting, &[&host.name])
+        );
+
+        if self.config.dry_run {
+            for line in self.render_dry_run(&host) {
+                println!("{line}");
+            }
+            return Ok(0);
+        }
+
+        let status = self
+            .session_templates()
+            .run(&host, ssh::StdioMode::Inherit)?;
+        if let Some(template) = self.config.after_command.as_ref() {
+            if status.success() {
+                host.run_command_template(
+                    template,
+                    ssh::Launcher::None,
+                    &[],
+                    ssh::StdioMode::Inherit,
+                )?;
+            }
+        }
+
+        Ok(if status.success() {
+            0
+        } else {
+            status.code().unwrap_or(1)
+        })
     }
 
     fn run<B>(&mut self, terminal: &Rc<RefCell<Terminal<B>>>) -> Result<()>
@@ -149,7 +397,23 @@ impl App {
         loop {
             terminal.borrow_mut().draw(|f| ui(f, self))?;
 
-            let ev = event::read()?;
+            let ev = match read_event(self.watcher.as_mut(), self.status_message_deadline)? {
+                Wakeup::Event(ev) => ev,
+                Wakeup::FilesChanged => {
+                    self.reload();
+                    continue;
+                }
+                Wakeup::Deadline => {
+                    self.status_message = None;
+                    self.status_message_deadline = None;
+                    continue;
+                }
+                Wakeup::Signal => {
+                    // The terminal is restored by `start`
+                    self.exit_code = 128 + PENDING_SIGNAL.load(Ordering::SeqCst);
+                    break;
+                }
+            };
 
             if let Event::Key(key) = ev {
                 if key.kind == KeyEventKind::Press {
@@ -162,15 +426,8 @@ impl App {
                 }
 
                 self.search.handle_event(&ev);
-                self.hosts.search(self.search.value());
-
-                let selected = self.table_state.selected().unwrap_or(0);
-                if selected >= self.hosts.len() {
-                    self.table_state.select(Some(match self.hosts.len() {
-                        0 => 0,
-                        _ => self.hosts.len() - 1,
-                    }));
-                }
+                self.search_history_index = None;
+                self.apply_search();
             }
         }
 
@@ -185,91 +442,530 @@ impl App {
     where
         B: Backend + std::io::Write,
     {
-        #[allow(clippy::enum_glob_use)]
-        use KeyCode::*;
+        self.status_message = None;
+        self.status_message_deadline = None;
+
+        // Any key closes the dry-run output
+        if self.dry_run_output.take().is_some() {
+            return Ok(AppKeyAction::Ok);
+        }
 
-        let is_ctrl_pressed = key.modifiers.contains(KeyModifiers::CONTROL);
+        if let Some(pending_key) = self.pending_key.take() {
+            self.on_pending_key_press(pending_key, key);
+            return Ok(AppKeyAction::Ok);
+        }
 
-        if is_ctrl_pressed {
-            let action = self.on_key_press_ctrl(key);
-            if action != AppKeyAction::Continue {
-                return Ok(action);
+        match self.key_action(&key) {
+            Some(action) => self.on_action(terminal, action),
+            // A search doesn't start with a space, so it can toggle the selection
+            None if key.code == KeyCode::Char(' ')
+                && key.modifiers.is_empty()
+                && self.search.value().is_empty() =>
+            {
+                self.toggle_selection();
+                Ok(AppKeyAction::Ok)
             }
+            None => Ok(AppKeyAction::Continue),
+        }
+    }
+
+    /// Action bound to the key. The actions that only apply while searching give the key back
+    /// to the others without a search.
+    fn key_action(&self, key: &KeyEvent) -> Option<Action> {
+        let is_searching = !self.search.value().is_empty();
+        let actions = self
+            .config
+            .keybindings
+            .actions(key)
+            .filter(|action| {
+                is_searching
+                    || !matches!(
+                        action,
+                        Action::NextMatch | Action::PreviousMatch | Action::SearchEnd
+                    )
+            })
+            .collect::<Vec<_>>();
+
+        // Keys bound to both (e.g. `ctrl+p`) move the selection unless the history is being
+        // cycled, which the arrows start when no host matches the search
+        let is_cycling_history = self.search_history_index.is_some();
+        actions
+            .iter()
+            .find(|action| {
+                matches!(action, Action::HistoryPrevious | Action::HistoryNext)
+                    == is_cycling_history
+            })
+            .or(actions.first())
+            .copied()
+    }
+
+    /// Filters the hosts with the search input, keeping the selection in the table.
+    ///
+    /// Favorites come first without a search.
+    fn apply_search(&mut self) {
+        self.hosts.search(self.search.value());
+        if self.search.value().is_empty() && !self.favorites.is_empty() {
+            let favorites = &self.favorites;
+            self.hosts
+                .sort_matches_by_key(|host| !favorites.contains(&host.name));
+        }
+        if let Some(max_results) = self.config.max_results {
+            self.hosts.truncate_matches(max_results);
+        }
+
+        let selected = self.table_state.selected().unwrap_or(0);
+        if selected >= self.hosts.len() {
+            self.table_state.select(Some(match self.hosts.len() {
+                0 => 0,
+                _ => self.hosts.len() - 1,
+            }));
+        }
+
+        // The columns empty for every matching host are hidden
+        self.calculate_table_columns_constraints();
+    }
+
+    fn set_search(&mut self, search: String) {
+        self.search = Input::new(search);
+        self.apply_search();
+    }
+
+    /// Edits the search like typing does, searching again when its value changes.
+    fn edit_search(&mut self, request: InputRequest) {
+        if self
+            .search
+            .handle(request)
+            .is_some_and(|changed| changed.value)
+        {
+            self.search_history_index = None;
+            self.apply_search();
         }
+    }
+
+    fn history_previous(&mut self) {
+        let index = self.search_history_index.map_or(0, |index| index + 1);
+        let Some(search) = self.search_history.get(index).map(str::to_string) else {
+            return;
+        };
 
-        match key.code {
-            Esc => return Ok(AppKeyAction::Stop),
-            Down => self.next(),
-            Up => self.previous(),
-            Home => self.table_state.select(Some(0)),
-            End => self.table_state.select(Some(self.hosts.len() - 1)),
-            PageDown => {
-                let i = self.table_state.selected().unwrap_or(0);
-                let target = min(i.saturating_add(21), self.hosts.len() - 1);
+        if self.search_history_index.is_none() {
+            self.search_draft = self.search.value().to_string();
+        }
+        self.search_history_index = Some(index);
+        self.set_search(search);
+    }
 
-                self.table_state.select(Some(target));
+    fn history_next(&mut self) {
+        match self.search_history_index {
+            None => {}
+            Some(0) => {
+                self.search_history_index = None;
+                let draft = std::mem::take(&mut self.search_draft);
+                self.set_search(draft);
+            }
+            Some(index) => {
+                self.search_history_index = Some(index - 1);
+                let search = self
+                    .search_history
+                    .get(index - 1)
+                    .unwrap_or_default()
+                    .to_string();
+                self.set_search(search);
             }
-            PageUp => {
-                let i = self.table_state.selected().unwrap_or(0);
-                let target = max(i.saturating_sub(21), 0);
+        }
+    }
+
+    /// Moves the selection, navigating an empty table is a no-op.
+    fn navigate(&mut self, action: Action) {
+        match action {
+            // Arrows cycle through the history when no host matches the search
+            Action::Down if self.hosts.is_empty() => self.history_next(),
+            Action::Up if self.hosts.is_empty() => self.history_previous(),
+            _ if self.hosts.is_empty() => {}
+            Action::Down | Action::NextMatch => self.next(),
+            Action::Up | Action::PreviousMatch => self.previous(),
+            Action::First => self.table_state.select(Some(0)),
+            Action::Last => self
+                .table_state
+                .select(Some(self.hosts.len().saturating_sub(1))),
+            Action::PageDown => self.scroll_down(self.page_size()),
+            Action::PageUp => self.scroll_up(self.page_size()),
+            Action::HalfPageDown => self.scroll_down(max(self.page_size() / 2, 1)),
+            Action::HalfPageUp => self.scroll_up(max(self.page_size() / 2, 1)),
+            _ => {}
+        }
+    }
+
+    fn on_action<B>(
+        &mut self,
+        terminal: &Rc<RefCell<Terminal<B>>>,
+        action: Action,
+    ) -> Result<AppKeyAction>
+    where
+        B: Backend + std::io::Write,
+    {
+        match action {
+            Action::Quit => return Ok(AppKeyAction::Stop),
+            Action::Down
+            | Action::Up
+            | Action::First
+            | Action::Last
+            | Action::PageDown
+            | Action::PageUp
+            | Action::HalfPageDown
+            | Action::HalfPageUp
+            | Action::NextMatch
+            | Action::PreviousMatch => self.navigate(action),
+            Action::Connect => {
+                if !self.selection.is_empty() {
+                    let hosts = self.selection_hosts();
+                    return self.connect(terminal, &hosts);
+                }
 
-                self.table_state.select(Some(target));
+                let host = self
+                    .unique_host()
+                    .or_else(|| self.selected_host())
+                    .cloned()
+                    .or_else(|| self.ad_hoc_host());
+                if let Some(host) = host {
+                    return self.connect(terminal, &[host]);
+                }
             }
-            Enter => {
-                let selected = self.table_state.selected().unwrap_or(0);
-                if selected >= self.hosts.len() {
-                    return Ok(AppKeyAction::Ok);
+            Action::ToggleProxy => {
+                self.config.show_proxy_command = !self.config.show_proxy_command;
+                self.calculate_table_columns_constraints();
+            }
+            Action::Copy => self.pending_key = Some('y'),
+            Action::Retry => {
+                let last_host = self.last_session.as_ref().and_then(|(name, _)| {
+                    self.hosts
+                        .non_filtered_iter()
+                        .find(|host| &host.name == name)
+                        .cloned()
+                });
+
+                if let Some(host) = last_host {
+                    return self.connect(terminal, &[host]);
                 }
+            }
+            Action::OpenInBrowser => self.open_in_browser(),
+            Action::HistoryPrevious => self.history_previous(),
+            Action::HistoryNext => self.history_next(),
+            Action::ToggleProxyChain => {
+                self.config.show_proxy_chain = !self.config.show_proxy_chain;
+            }
+            Action::ReverseSort => self.reverse_sort(),
+            Action::ToggleAliases => self.expand_aliases = !self.expand_aliases,
+            Action::DeleteWord => self.edit_search(InputRequest::DeletePrevWord),
+            Action::SearchStart => self.edit_search(InputRequest::GoToStart),
+            Action::SearchEnd => self.edit_search(InputRequest::GoToEnd),
+            Action::ToggleFavorite => self.toggle_favorite(),
+            Action::ToggleSelection => self.toggle_selection(),
+            Action::NextPreset => {
+                self.active_preset =
+                    (self.active_preset + 1) % (self.config.command_template_presets.len() + 1);
+            }
+        }
+
+        Ok(AppKeyAction::Ok)
+    }
 
-                let host: &ssh::Host = &self.hosts[selected];
+    /// Handles the second key of a two keys binding, any other key cancels the binding.
+    fn on_pending_key_press(&mut self, pending_key: char, key: KeyEvent) {
+        let selected = self.table_state.selected().unwrap_or(0);
+        if selected >= self.hosts.len() {
+            return;
+        }
 
-                restore_terminal(terminal).expect("Failed to restore terminal");
+        let host = &self.hosts[selected];
+        let (text, copied) = match (pending_key, key.code) {
+            ('y', KeyCode::Char('n')) => (host.name.clone(), None),
+            ('y', KeyCode::Char('d')) => match &host.user {
+                Some(user) => (format!("{user}@{}", host.destination), None),
+                None => (host.destination.clone(), None),
+            },
+            // `key value` lines like `ssh -G`, too long for the footer
+            ('y', KeyCode::Char('c')) => (
+                host.entries
+                    .iter()
+                    .fold(String::new(), |mut text, (key, value)| {
+                        let _ = writeln!(text, "{key} {value}");
+                        text
+                    }),
+                Some(messages::format(self.messages.copied_config, &[&host.name])),
+            ),
+            _ => return,
+        };
 
-                if let Some(template) = &self.config.command_template_on_session_start {
-                    host.run_command_template(template)?;
-                }
+        self.status_message = Some(match self.config.clipboard.copy(&text) {
+            Ok(()) => copied.unwrap_or_else(|| messages::format(self.messages.copied, &[&text])),
+            Err(err) => messages::format(self.messages.copy_failed, &[&err]),
+        });
+    }
 
-                host.run_command_template(&self.config.command_template)?;
+    /// Pins or unpins the selected host, keeping it selected.
+    fn toggle_favorite(&mut self) {
+        let Some(name) = self.selected_host().map(|host| host.name.clone()) else {
+            return;
+        };
 
-                if let Some(template) = &self.config.command_template_on_session_end {
-                    host.run_command_template(template)?;
-                }
+        if let Err(err) = self.favorites.toggle(&name) {
+            self.status_message = Some(messages::format(
+                self.messages.favorites_save_failed,
+                &[&err],
+            ));
+        }
 
-                setup_terminal(terminal).expect("Failed to setup terminal");
+        self.apply_search();
+        self.table_state
+            .select(self.hosts.iter().position(|host| host.name == name));
+    }
 
-                if self.config.exit_after_ssh_session_ends {
-                    return Ok(AppKeyAction::Stop);
-                }
+    /// Parses the SSH configuration files again, keeping the selected hosts selected.
+    fn reload(&mut self) {
+        let (hosts, included_paths) = match load_hosts(&self.config) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                self.status_message = Some(messages::format(self.messages.reload_failed, &[&err]));
+                self.status_message_deadline = None;
+                return;
             }
-            _ => return Ok(AppKeyAction::Continue),
+        };
+
+        if let Some(watcher) = &mut self.watcher {
+            watcher.watch(watched_paths(&self.config, &included_paths));
         }
 
-        Ok(AppKeyAction::Ok)
+        let name = self.selected_host().map(|host| host.name.clone());
+        self.selection
+            .retain(|name| hosts.iter().any(|host| &host.name == name));
+
+        self.hosts.replace(hosts, self.search.value());
+        self.apply_search();
+        if let Some(name) = name {
+            if let Some(index) = self.hosts.iter().position(|host| host.name == name) {
+                self.table_state.select(Some(index));
+            }
+        }
+
+        self.status_message = Some(self.messages.reloaded.to_string());
+        self.status_message_deadline = Some(Instant::now() + RELOADED_MESSAGE_DURATION);
+    }
+
+    /// Switches the sort direction, keeping the selected host selected.
+    fn reverse_sort(&mut self) {
+        let name = self.selected_host().map(|host| host.name.clone());
+
+        self.config.sort_dir = self.config.sort_dir.reversed();
+        self.hosts.reverse();
+        self.apply_search();
+
+        if let Some(name) = name {
+            self.table_state
+                .select(self.hosts.iter().position(|host| host.name == name));
+        }
+    }
+
+    /// Adds the highlighted host to the selection, or removes it from it.
+    fn toggle_selection(&mut self) {
+        let Some(name) = self.selected_host().map(|host| host.name.clone()) else {
+            return;
+        };
+
+        if !self.selection.remove(&name) {
+            self.selection.insert(name);
+        }
+        self.calculate_table_columns_constraints();
+    }
+
+    /// Hosts of the selection in the table order, including the ones hidden by the search.
+    fn selection_hosts(&self) -> Vec<ssh::Host> {
+        self.hosts
+            .non_filtered_iter()
+            .filter(|host| self.selection.contains(&host.name))
+            .cloned()
+            .collect()
+    }
+
+    /// Opens the selected host in the browser if it is tagged as a web host.
+    fn open_in_browser(&mut self) {
+        let Some(host) = self.selected_host() else {
+            return;
+        };
+
+        let Some(url) = host.web_url() else {
+            self.status_message = Some(messages::format(
+                self.messages.not_tagged,
+                &[&host.name, &ssh::WEB_TAG],
+            ));
+            return;
+        };
+
+        self.status_message = Some(match browser::open(&url) {
+            Ok(()) => messages::format(self.messages.opened, &[&url]),
+            Err(err) => messages::format(self.messages.open_failed, &[&url, &err]),
+        });
+    }
+
+    /// Host to connect to when the search is a `[user@]host[:port]` matching no configured host.
+    fn ad_hoc_host(&self) -> Option<ssh::Host> {
+        if !self.hosts.is_empty() {
+            return None;
+        }
+
+        let mut host = ssh::Host::ad_hoc(self.search.value().trim())?;
+        if let Some(timeout) = self.config.connect_timeout {
+            host.connect_timeout = Some(timeout.to_string());
+        }
+        Some(host)
+    }
+
+    /// Host to connect to with `enter_on_unique`: the only host matching the search,
+    /// or else the only one named or aliased exactly like the search.
+    fn unique_host(&self) -> Option<&ssh::Host> {
+        if !self.config.enter_on_unique {
+            return None;
+        }
+
+        if self.hosts.len() == 1 {
+            return self.hosts.iter().next();
+        }
+
+        let search = self.search.value().trim();
+        if search.is_empty() {
+            return None;
+        }
+
+        let mut named = self.hosts.iter().filter(|host| host.is_named(search));
+        match (named.next(), named.next()) {
+            (Some(host), None) => Some(host),
+            _ => None,
+        }
+    }
+
+    fn selected_host(&self) -> Option<&ssh::Host> {
+        let selected = self.table_state.selected().unwrap_or(0);
+        if selected >= self.hosts.len() {
+            return None;
+        }
+
+        Some(&self.hosts[selected])
     }
 
-    fn on_key_press_ctrl(&mut self, key: KeyEvent) -> AppKeyAction {
-        #[allow(clippy::enum_glob_use)]
-        use KeyCode::*;
+    /// Starts a session on each of the `hosts` one after the other, or shows the commands that
+    /// would run in dry-run mode.
+    ///
+    /// The selection is cleared once the sessions are started.
+    fn connect<B>(
+        &mut self,
+        terminal: &Rc<RefCell<Terminal<B>>>,
+        hosts: &[ssh::Host],
+    ) -> Result<AppKeyAction>
+    where
+        B: Backend + std::io::Write,
+    {
+        if self.config.dry_run {
+            self.dry_run_output = Some(match hosts {
+                [host] => self.render_dry_run(host),
+                hosts => hosts
+                    .iter()
+                    .flat_map(|host| {
+                        let lines = self.render_dry_run(host).into_iter();
+                        std::iter::once(format!("{}:", host.name))
+                            .chain(lines.map(|line| format!("  {line}")))
+                    })
+                    .collect(),
+            });
+            return Ok(AppKeyAction::Ok);
+        }
+
+        if let Err(err) = self.search_history.push(self.search.value()) {
+            self.status_message = Some(messages::format(
+                self.messages.search_history_save_failed,
+                &[&err],
+            ));
+        }
+
+        if !self.selection.is_empty() {
+            self.selection.clear();
+            self.calculate_table_columns_constraints();
+        }
+
+        if self.config.detach {
+            for host in hosts {
+                self.start_detached_session(host);
+            }
+            let names = hosts
+                .iter()
+                .map(|host| host.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.status_message = Some(messages::format(self.messages.detached, &[&names]));
+            self.last_session = None;
+            return Ok(AppKeyAction::Ok);
+        }
 
-        match key.code {
-            Char('c') => AppKeyAction::Stop,
-            Char('j' | 'n') => {
-                self.next();
-                AppKeyAction::Ok
+        restore_terminal(terminal).expect("Failed to restore terminal");
+
+        let mut last_session: Option<(String, ExitStatus)> = None;
+        for host in hosts {
+            let status = self
+                .session_templates()
+                .run(host, ssh::StdioMode::Inherit)?;
+
+            if let Some(template) = self
+                .config
+                .after_command
+                .as_ref()
+                .filter(|_| status.success())
+            {
+                host.run_command_template(
+                    template,
+                    ssh::Launcher::None,
+                    &[],
+                    ssh::StdioMode::Inherit,
+                )?;
             }
-            Char('k' | 'p') => {
-                self.previous();
-                AppKeyAction::Ok
+
+            // The first failed session is the one to retry
+            if last_session
+                .as_ref()
+                .is_none_or(|(_, status)| status.success())
+            {
+                last_session = Some((host.name.clone(), status));
             }
-            _ => AppKeyAction::Continue,
         }
+
+        setup_terminal(terminal).expect("Failed to setup terminal");
+
+        self.last_session = last_session;
+        let Some((_, status)) = self.last_session else {
+            return Ok(AppKeyAction::Ok);
+        };
+
+        if self.config.exit_after_ssh_session_ends {
+            self.exit_code = if status.success() {
+                0
+            } else {
+                status.code().unwrap_or(1)
+            };
+            return Ok(AppKeyAction::Stop);
+        }
+
+        Ok(AppKeyAction::Ok)
     }
 
     fn next(&mut self) {
         let i = match self.table_state.selected() {
             Some(i) => {
-                if self.hosts.is_empty() || i >= self.hosts.len() - 1 {
+                if self.hosts.is_empty() {
                     0
+                } else if i >= self.hosts.len() - 1 {
+                    if self.config.no_wrap {
+                        self.hosts.len() - 1
+                    } else {
+                        0
+                    }
                 } else {
                     i + 1
                 }
@@ -285,7 +981,11 @@ impl App {
                 if self.hosts.is_empty() {
                     0
                 } else if i == 0 {
-                    self.hosts.len() - 1
+                    if self.config.no_wrap {
+                        0
+                    } else {
+                        self.hosts.len() - 1
+                    }
                 } else {
                     i - 1
                 }
@@ -295,74 +995,123 @@ impl App {
         self.table_state.select(Some(i));
     }
 
-    fn calculate_table_columns_constraints(&mut self) {
-        let mut lengths = Vec::new();
+    /// Moves the selection `rows` down, stopping at the last host.
+    fn scroll_down(&mut self, rows: usize) {
+        let i = self.table_state.selected().unwrap_or(0);
+        let target = min(i.saturating_add(rows), self.hosts.len().saturating_sub(1));
 
-        let name_len = self
-            .hosts
-            .iter()
-            .map(|d| d.name.as_str())
-            .map(UnicodeWidthStr::width)
-            .max()
-            .unwrap_or(0);
-        lengths.push(name_len);
+        self.table_state.select(Some(target));
+    }
 
-        let aliases_len = self
-            .hosts
-            .non_filtered_iter()
-            .map(|d| d.aliases.as_str())
-            .map(UnicodeWidthStr::width)
-            .max()
-            .unwrap_or(0);
-        lengths.push(aliases_len);
+    /// Moves the selection `rows` up, stopping at the first host.
+    fn scroll_up(&mut self, rows: usize) {
+        let i = self.table_state.selected().unwrap_or(0);
+        let target = i.saturating_sub(rows);
 
-        let user_len = self
-            .hosts
-            .non_filtered_iter()
-            .map(|d| match &d.user {
-                Some(user) => user.as_str(),
-                None => "",
-            })
-            .map(UnicodeWidthStr::width)
-            .max()
-            .unwrap_or(0);
-        lengths.push(user_len);
+        self.table_state.select(Some(target));
+    }
+
+    /// Number of rows visible in the last rendered table.
+    fn page_size(&self) -> usize {
+        // -2 for the borders, -1 for the header
+        max(usize::from(self.table_area.height.saturating_sub(3)), 1)
+    }
+
+    /// Columns displayed in the table, in order.
+    ///
+    /// The proxy and source columns are added to the configured columns when toggled on.
+    fn table_columns(&self) -> Vec<ssh::HostField> {
+        if !self.config.columns.is_empty() {
+            let mut columns = self.config.columns.clone();
+            for (column, is_shown) in [
+                (ssh::HostField::ProxyCommand, self.config.show_proxy_command),
+                (ssh::HostField::Source, self.config.show_source),
+                (ssh::HostField::Description, self.config.show_description),
+            ] {
+                if is_shown && !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+            return self.retain_non_empty_columns(columns);
+        }
 
-        let destination_len = self
+        let mut columns = vec![
+            ssh::HostField::Name,
+            ssh::HostField::Aliases,
+            ssh::HostField::User,
+            ssh::HostField::Destination,
+            ssh::HostField::Port,
+        ];
+        if self.config.show_proxy_command {
+            columns.push(ssh::HostField::ProxyCommand);
+        }
+        if self.config.show_source {
+            columns.push(ssh::HostField::Source);
+        }
+        if self.config.show_description {
+            columns.push(ssh::HostField::Description);
+        }
+        if self
             .hosts
             .non_filtered_iter()
-            .map(|d| d.destination.as_str())
-            .map(UnicodeWidthStr::width)
-            .max()
-            .unwrap_or(0);
-        lengths.push(destination_len);
-
-        let port_len = self
+            .any(|host| host.label.is_some())
+        {
+            columns.push(ssh::HostField::Label);
+        }
+        if self
             .hosts
             .non_filtered_iter()
-            .map(|d| match &d.port {
-                Some(port) => port.as_str(),
-                None => "",
-            })
-            .map(UnicodeWidthStr::width)
-            .max()
-            .unwrap_or(0);
-        lengths.push(port_len);
+            .any(|host| !host.tags.is_empty())
+        {
+            columns.push(ssh::HostField::Tags);
+        }
 
-        if self.config.show_proxy_command {
-            let proxy_len = self
-                .hosts
-                .non_filtered_iter()
-                .map(|d| match &d.proxy_command {
-                    Some(proxy) => proxy.as_str(),
-                    None => "",
-                })
-                .map(UnicodeWidthStr::width)
-                .max()
-                .unwrap_or(0);
-            lengths.push(proxy_len);
+        self.retain_non_empty_columns(columns)
+    }
+
+    /// Removes the columns empty for every host matching the search, except the name, unless no
+    /// host matches.
+    fn retain_non_empty_columns(&self, mut columns: Vec<ssh::HostField>) -> Vec<ssh::HostField> {
+        if self.hosts.is_empty() {
+            return columns;
         }
 
+        columns.retain(|&column| {
+            column == ssh::HostField::Name
+                || self
+                    .hosts
+                    .iter()
+                    .any(|host| !column_value(host, column, &self.config).is_empty())
+        });
+        columns
+    }
+
+    fn calculate_table_columns_constraints(&mut self) {
+        let lengths = self
+            .table_columns()
+            .into_iter()
+            .map(|column| {
+                let mut length = self
+                    .hosts
+                    .non_filtered_iter()
+                    .map(|host| column_value(host, column, &self.config).width())
+                    .max()
+                    .unwrap_or(0);
+                if column == ssh::HostField::Name {
+                    if !self.favorites.is_empty() {
+                        length += FAVORITE_MARKER.width();
+                    }
+                    if !self.selection.is_empty() {
+                        length += SELECTION_MARKER.width();
+                    }
+                }
+                max(
+                    length,
+                    usize::from(self.config.min_column_widths.get(column)),
+                )
+            })
+            .collect::<Vec<_>>();
+
         let mut new_constraints = vec![
             // +1 for padding
             Constraint::Length(u16::try_from(lengths[0]).unwrap_or_default() + 1),
@@ -373,67 +1122,767 @@ impl App {
                 .skip(1)
                 .map(|len| Constraint::Min(u16::try_from(*len).unwrap_or_default() + 1)),
         );
+
+        self.table_columns_constraints = new_constraints;
     }
-}
 
-fn setup_terminal<B>(terminal: &Rc<RefCell<Terminal<B>>>) -> Result<()>
-where
-    B: Backend + std::io::Write,
-{
-    let mut terminal = terminal.borrow_mut();
+    fn session_templates(&self) -> SessionTemplates {
+        SessionTemplates {
+            on_session_start: self.config.command_template_on_session_start.clone(),
+            command: self.active_command_template().1.to_string(),
+            on_session_end: self.config.command_template_on_session_end.clone(),
+            launcher: self.config.launcher,
+            ssh_args: self.config.ssh_args.clone(),
+        }
+    }
 
-    // setup terminal
-    enable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        Hide,
-        EnterAlternateScreen,
-        EnableMouseCapture
-    )?;
+    /// Waits for the sessions still running in the background, which would otherwise end with
+    /// sshs without notifying.
+    fn wait_for_detached_sessions(&mut self) {
+        self.detached_sessions
+            .retain(|session| !session.is_finished());
+        if self.detached_sessions.is_empty() {
+            return;
+        }
 
-    Ok(())
-}
+        eprintln!(
+            "{}",
+            messages::format(
+                self.messages.waiting_for_detached_sessions,
+                &[&self.detached_sessions.len()]
+            )
+        );
+        for session in self.detached_sessions.drain(..) {
+            let _ = session.join();
+        }
+    }
 
-fn restore_terminal<B>(terminal: &Rc<RefCell<Terminal<B>>>) -> Result<()>
-where
-    B: Backend + std::io::Write,
-{
-    let mut terminal = terminal.borrow_mut();
-    terminal.clear()?;
+    /// Runs the session of `host` and its after command in a background thread,
+    /// then notifies when the session ends.
+    fn start_detached_session(&mut self, host: &ssh::Host) {
+        let host = host.clone();
+        let session_templates = self.session_templates();
+        let after_command = self.config.after_command.clone();
+        let messages = self.messages;
+
+        self.detached_sessions
+            .retain(|session| !session.is_finished());
+        self.detached_sessions.push(std::thread::spawn(move || {
+            let result = session_templates
+                .run(&host, ssh::StdioMode::Null)
+                .and_then(|status| match &after_command {
+                    Some(template) if status.success() => {
+                        host.run_command_template(
+                            template,
+                            ssh::Launcher::None,
+                            &[],
+                            ssh::StdioMode::Null,
+                        )?;
+                        Ok(status)
+                    }
+                    _ => Ok(status),
+                });
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        Show,
-        LeaveAlternateScreen,
-        DisableMouseCapture,
-    )?;
+            let body = match result {
+                Ok(status) if status.success() => {
+                    messages::format(messages.session_ended, &[&host.name])
+                }
+                Ok(status) => match status.code() {
+                    Some(code) => messages::format(messages.session_exited, &[&host.name, &code]),
+                    None => messages::format(messages.session_terminated, &[&host.name]),
+                },
+                Err(err) => messages::format(messages.session_failed, &[&host.name, &err]),
+            };
+
+            // Nothing can show the error once the session is detached
+            let _ = notification::send("sshs", &body);
+        }));
+    }
+
+    /// Name and template of the active preset.
+    fn active_command_template(&self) -> (&str, &str) {
+        match self
+            .active_preset
+            .checked_sub(1)
+            .and_then(|index| self.config.command_template_presets.get(index))
+        {
+            Some((name, template)) => (name, template),
+            None => (presets::DEFAULT_PRESET_NAME, &self.config.command_template),
+        }
+    }
+
+    /// Renders every template that would run for `host` without executing them.
+    fn render_dry_run(&self, host: &ssh::Host) -> Vec<String> {
+        let templates = [
+            (
+                self.messages.on_session_start,
+                self.config.command_template_on_session_start.as_deref(),
+                ssh::Launcher::None,
+                &[][..],
+            ),
+            (
+                self.messages.command,
+                Some(host.command_template(self.active_command_template().1)),
+                self.config.launcher,
+                &self.config.ssh_args,
+            ),
+            (
+                self.messages.on_session_end,
+                self.config.command_template_on_session_end.as_deref(),
+                ssh::Launcher::None,
+                &[],
+            ),
+            (
+                self.messages.after_command,
+                self.config.after_command.as_deref(),
+                ssh::Launcher::None,
+                &[],
+            ),
+        ];
+
+        let mut lines = Vec::new();
+        for (label, template, launcher, extra_args) in templates {
+            let Some(template) = template else {
+                continue;
+            };
+
+            match host.render_command_template(template, launcher, extra_args) {
+                Ok((rendered_command, args)) => {
+                    lines.push(format!("{label}: {rendered_command}"));
+                    lines.push(format!("  argv: {args:?}"));
+                }
+                Err(err) => lines.push(format!("{label}: {err}")),
+            }
+        }
+
+        lines
+    }
+
+    /// Lines of the proxy chain of the selected host, each hop indented under the previous one.
+    fn render_proxy_chain(&self) -> Vec<String> {
+        let Some(host) = self.selected_host() else {
+            return Vec::new();
+        };
+
+        let chain = host.proxy_chain(self.hosts.non_filtered_iter().as_slice());
+
+        let mut lines = vec![format!("{} ({})", host.name, host.destination)];
+        if chain.is_empty() {
+            lines.push(format!("└ {}", self.messages.direct_connection));
+        }
+
+        for (depth, hop) in chain.iter().enumerate() {
+            let hop = match hop {
+                ssh::ProxyHop::Jump {
+                    name,
+                    destination: Some(destination),
+                } => format!("{name} ({destination})"),
+                ssh::ProxyHop::Jump {
+                    name,
+                    destination: None,
+                } => format!("{name} ({})", self.messages.not_configured),
+                ssh::ProxyHop::Command(command) => format!("ProxyCommand {command}"),
+                ssh::ProxyHop::Cycle(name) => messages::format(self.messages.proxy_cycle, &[name]),
+            };
+            lines.push(format!("{}└ {hop}", "  ".repeat(depth)));
+        }
+
+        lines
+    }
+
+    /// Hint of the action with its first key, `None` if the action isn't bound to any key.
+    fn hint(&self, action: Action, text: &str) -> Option<String> {
+        self.config
+            .keybindings
+            .key(action)
+            .map(|key| format!("({key}) {text}"))
+    }
+
+    /// Builds the list of key hints for the actions currently available.
+    fn footer_hints(&self) -> Vec<String> {
+        if self.dry_run_output.is_some() {
+            return vec![format!(
+                "({}) {}",
+                self.messages.any_key, self.messages.close
+            )];
+        }
+
+        if self.pending_key.is_some() {
+            return vec![
+                format!("(n) {}", self.messages.copy_name),
+                format!("(d) {}", self.messages.copy_destination),
+                format!("(c) {}", self.messages.copy_config),
+                format!("({}) {}", self.messages.any_key, self.messages.cancel),
+            ];
+        }
+
+        let mut hints = Vec::new();
+
+        if let Some(status_message) = &self.status_message {
+            hints.push(status_message.clone());
+        }
+
+        if self.hosts.match_count() > self.hosts.len() {
+            hints.push(messages::format(
+                self.messages.showing_results,
+                &[&self.hosts.len(), &self.hosts.match_count()],
+            ));
+        }
+
+        if let Some((name, status)) = &self.last_session {
+            if !status.success() {
+                match status.code() {
+                    Some(code) => hints.push(messages::format(
+                        self.messages.last_session_exited,
+                        &[name, &code],
+                    )),
+                    None => hints.push(messages::format(
+                        self.messages.last_session_terminated,
+                        &[name],
+                    )),
+                }
+                hints.extend(self.hint(Action::Retry, self.messages.retry));
+            }
+        }
+
+        hints.extend(self.hint(Action::Quit, self.messages.quit));
+
+        if !self.hosts.is_empty() {
+            hints.extend(self.hint(Action::Up, self.messages.move_up));
+            hints.extend(self.hint(Action::Down, self.messages.move_down));
+            match self.unique_host() {
+                _ if !self.selection.is_empty() => hints.extend(self.hint(
+                    Action::Connect,
+                    &messages::format(self.messages.connect_to_selection, &[&self.selection.len()]),
+                )),
+                Some(host) => hints.extend(self.hint(
+                    Action::Connect,
+                    &messages::format(self.messages.connect_to, &[&host.name]),
+                )),
+                None => hints.extend(self.hint(Action::Connect, self.messages.select)),
+            }
+        }
+
+        if let Some(host) = self.selected_host() {
+            let text = if self.favorites.contains(&host.name) {
+                self.messages.remove_favorite
+            } else {
+                self.messages.add_favorite
+            };
+            hints.extend(self.hint(Action::ToggleFavorite, text));
+
+            let text = if self.selection.contains(&host.name) {
+                self.messages.remove_from_selection
+            } else {
+                self.messages.add_to_selection
+            };
+            hints.extend(self.hint(Action::ToggleSelection, text));
+        }
+
+        if let Some(host) = self.ad_hoc_host() {
+            hints.extend(self.hint(
+                Action::Connect,
+                &messages::format(self.messages.connect_to_ad_hoc, &[&host.name]),
+            ));
+        }
+
+        if self
+            .selected_host()
+            .is_some_and(|host| host.web_url().is_some())
+        {
+            hints.extend(self.hint(Action::OpenInBrowser, self.messages.open_in_browser));
+        }
+
+        // The preset key moves through the matches while searching when they share it
+        let keybindings = &self.config.keybindings;
+        let is_preset_key_taken = !self.search.value().is_empty()
+            && keybindings.key(Action::NextPreset) == keybindings.key(Action::NextMatch);
+        if !self.config.command_template_presets.is_empty() && !is_preset_key_taken {
+            let (name, _) = self.active_command_template();
+            hints.extend(self.hint(
+                Action::NextPreset,
+                &messages::format(self.messages.preset, &[&name]),
+            ));
+        }
+
+        hints.extend(self.view_hints());
+
+        hints
+    }
+
+    /// Hints of the actions changing how the hosts are displayed.
+    fn view_hints(&self) -> Vec<String> {
+        let mut hints = Vec::new();
+
+        hints.extend(self.hint(
+            Action::ToggleProxy,
+            if self.config.show_proxy_command {
+                self.messages.hide_proxy
+            } else {
+                self.messages.show_proxy
+            },
+        ));
+
+        hints.extend(self.hint(
+            Action::ReverseSort,
+            match self.config.sort_dir {
+                ssh::SortDir::Asc => self.messages.sort_descending,
+                ssh::SortDir::Desc => self.messages.sort_ascending,
+            },
+        ));
+
+        if self
+            .hosts
+            .non_filtered_iter()
+            .any(|host| !host.aliases.is_empty())
+        {
+            hints.extend(self.hint(
+                Action::ToggleAliases,
+                if self.expand_aliases {
+                    self.messages.collapse_aliases
+                } else {
+                    self.messages.expand_aliases
+                },
+            ));
+        }
+
+        hints.extend(self.hint(
+            Action::ToggleProxyChain,
+            if self.config.show_proxy_chain {
+                self.messages.hide_proxy_chain
+            } else {
+                self.messages.show_proxy_chain
+            },
+        ));
+
+        hints
+    }
+}
+
+/// Templates of a session, owned so the session can run in the background.
+struct SessionTemplates {
+    on_session_start: Option<String>,
+    command: String,
+    on_session_end: Option<String>,
+    launcher: ssh::Launcher,
+    ssh_args: Vec<String>,
+}
+
+impl SessionTemplates {
+    /// Runs the session templates for `host` and returns the exit status of the session.
+    ///
+    /// The session isn't started if the session start template fails.
+    fn run(&self, host: &ssh::Host, stdio: ssh::StdioMode) -> Result<ExitStatus> {
+        let run = |template: &str, launcher: ssh::Launcher, extra_args: &[String]| {
+            host.run_command_template(template, launcher, extra_args, stdio)
+        };
+
+        if let Some(template) = &self.on_session_start {
+            let status = run(template, ssh::Launcher::None, &[])?;
+            if !status.success() {
+                return Ok(status);
+            }
+        }
+
+        let status = run(
+            host.command_template(&self.command),
+            self.launcher,
+            &self.ssh_args,
+        )?;
+
+        if let Some(template) = &self.on_session_end {
+            run(template, ssh::Launcher::None, &[])?;
+        }
+
+        Ok(status)
+    }
+}
+
+/// Parses the SSH configuration files into the hosts of the table.
+/// Parses the hosts of the SSH configuration files, with the paths of the files they include.
+fn load_hosts(config: &AppConfig) -> Result<(Vec<ssh::Host>, Vec<PathBuf>)> {
+    let parser = hosts_parser(config);
+    let mut hosts = Vec::new();
+
+    for parsed_hosts in ssh::parse_configs(&config.config_paths, &config.include_dirs, &parser) {
+        match parsed_hosts {
+            Ok(parsed_hosts) => hosts.extend(parsed_hosts),
+            Err(err) => anyhow::bail!("Failed to parse SSH configuration file: {err}"),
+        }
+    }
+
+    Ok((prepare_hosts(config, hosts), parser.included_paths()))
+}
+
+fn hosts_parser(config: &AppConfig) -> ssh_config::Parser {
+    let mut parser = ssh_config::Parser::new()
+        .ignore_unknown_entries(!config.strict)
+        .ignore_missing_includes(!config.strict)
+        .merge_same_hosts(config.merge_same_hosts)
+        .include_allow_roots(&config.include_allow_roots);
+    if let Some(prefix) = &config.tags_annotation_prefix {
+        parser = parser.tags_annotation_prefix(prefix);
+    }
+
+    parser
+}
+
+/// Filters, labels and sorts the parsed hosts for the table.
+fn prepare_hosts(config: &AppConfig, mut hosts: Vec<ssh::Host>) -> Vec<ssh::Host> {
+    if config.require_hostname {
+        hosts.retain(|host| host.has_explicit_hostname);
+    }
+
+    for host in &mut hosts {
+        if let Some(label) = config.labels.find(&host.name) {
+            host.label.clone_from(&label.text);
+            host.label_color = label.color.map(|color| color.to_string());
+        }
+        if let Some(timeout) = config.connect_timeout {
+            host.connect_timeout = Some(timeout.to_string());
+        }
+    }
+
+    config.sort_by.sort(&mut hosts, config.sort_dir);
+
+    hosts
+}
+
+/// Paths whose changes reload the hosts: the configuration files, the include directories
+/// and every included file, those defining no host included.
+///
+/// The configuration read from stdin doesn't change, only the files it includes are watched.
+fn watched_paths(config: &AppConfig, included_paths: &[PathBuf]) -> Vec<PathBuf> {
+    config
+        .config_paths
+        .iter()
+        .filter(|path| *path != ssh::STDIN_CONFIG_PATH)
+        .chain(&config.include_dirs)
+        .map(|path| ssh::expand_path(path))
+        .chain(included_paths.iter().cloned())
+        .collect()
+}
+
+/// Text displayed in the table for the host column.
+fn column_value(host: &ssh::Host, column: ssh::HostField, config: &AppConfig) -> String {
+    match column {
+        ssh::HostField::Destination if config.destination_with_user => match &host.user {
+            Some(user) => format!("{user}@{}", host.destination),
+            None => host.destination.clone(),
+        },
+        ssh::HostField::Port => match (&host.port, config.default_port) {
+            (None, Some(default_port)) => default_port.to_string(),
+            (port, _) => port.clone().unwrap_or_default(),
+        },
+        ssh::HostField::Source => host
+            .source_path
+            .as_deref()
+            .map(shorten_home)
+            .unwrap_or_default(),
+        _ => column.value(host).unwrap_or_default().into_owned(),
+    }
+}
+
+/// Name shown in the table, or the alias matching the search better with
+/// `--show-matched-alias`, after the selection and favorite markers.
+fn name_cell_value(app: &App, host: &ssh::Host, matcher: &SkimMatcherV2) -> String {
+    let alias = if app.config.show_matched_alias {
+        host.matched_alias(matcher, app.search.value())
+    } else {
+        None
+    };
+
+    let mut value = alias.unwrap_or(&host.name).to_string();
+    if app.favorites.contains(&host.name) {
+        value.insert_str(0, FAVORITE_MARKER);
+    }
+    if app.selection.contains(&host.name) {
+        value.insert_str(0, SELECTION_MARKER);
+    }
+    value
+}
+
+/// Aliases fitting in `width` columns followed by the count of the others, e.g. `a, b (+3)`.
+fn collapse_aliases(aliases: &str, width: usize) -> String {
+    if aliases.width() <= width {
+        return aliases.to_string();
+    }
+
+    let aliases = aliases.split(", ").collect::<Vec<_>>();
+    let mut shown = 0;
+    while shown < aliases.len() {
+        let candidate = format!(
+            "{} (+{})",
+            aliases[..=shown].join(", "),
+            aliases.len() - shown - 1
+        );
+        if candidate.width() > width {
+            break;
+        }
+        shown += 1;
+    }
+
+    match shown {
+        0 => format!("(+{})", aliases.len()),
+        _ => format!(
+            "{} (+{})",
+            aliases[..shown].join(", "),
+            aliases.len() - shown
+        ),
+    }
+}
+
+/// Aliases on as many lines of `width` columns as needed, each line but the last ending with a
+/// comma.
+fn wrap_aliases(aliases: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for alias in aliases.split(", ") {
+        match lines.last_mut() {
+            // +2 for the comma ending the line and the space before the alias
+            Some(line) if line.width() + alias.width() + 2 <= width => {
+                line.push_str(", ");
+                line.push_str(alias);
+            }
+            _ => {
+                if let Some(line) = lines.last_mut() {
+                    line.push(',');
+                }
+                lines.push(alias.to_string());
+            }
+        }
+    }
+
+    lines
+}
+
+/// Truncates the text to `width` columns, ending it with an ellipsis when truncated.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut truncated_width = ELLIPSIS.width();
+    for c in text.chars() {
+        truncated_width += c.width().unwrap_or(0);
+        if truncated_width > width {
+            break;
+        }
+
+        truncated.push(c);
+    }
+    truncated.push_str(ELLIPSIS);
+
+    truncated
+}
+
+/// Replaces the home directory prefix of the path with `~`.
+fn shorten_home(path: &Path) -> String {
+    let home = PathBuf::from(shellexpand::tilde("~").as_ref());
+
+    match path.strip_prefix(&home) {
+        Ok(relative_path) if home.as_os_str().len() > 1 => {
+            Path::new("~").join(relative_path).display().to_string()
+        }
+        _ => path.display().to_string(),
+    }
+}
+
+/// Joins the hints with [`INFO_SEPARATOR`], dropping the trailing ones that don't fit in `width`.
+fn fit_hints(hints: &[String], width: usize) -> String {
+    let full = hints.join(INFO_SEPARATOR);
+    if full.width() <= width {
+        return full;
+    }
+
+    let mut text = String::new();
+    for hint in hints {
+        let candidate = if text.is_empty() {
+            hint.clone()
+        } else {
+            format!("{text}{INFO_SEPARATOR}{hint}")
+        };
+
+        if candidate.width() + INFO_SEPARATOR.width() + ELLIPSIS.width() > width {
+            break;
+        }
+
+        text = candidate;
+    }
+
+    if text.is_empty() {
+        return ELLIPSIS.to_string();
+    }
+
+    format!("{text}{INFO_SEPARATOR}{ELLIPSIS}")
+}
+
+/// Exits on SIGINT and SIGTERM.
+///
+/// The terminal can only be restored by the thread owning it, so while it is set up the signal
+/// is left to the run loop which stops on the next [`read_event`].
+#[cfg(unix)]
+fn handle_termination_signals() -> Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            if IS_TERMINAL_SETUP.load(Ordering::SeqCst) {
+                PENDING_SIGNAL.store(signal, Ordering::SeqCst);
+            } else {
+                std::process::exit(128 + signal);
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// What [`read_event`] stopped waiting for.
+enum Wakeup {
+    Event(Event),
+    /// A termination signal is pending.
+    Signal,
+    /// The files of the watcher changed.
+    FilesChanged,
+    /// The deadline passed.
+    Deadline,
+}
+
+/// Waits for the next event, a termination signal, a change to the watched files or the
+/// `deadline`.
+fn read_event(mut watcher: Option<&mut FileWatcher>, deadline: Option<Instant>) -> Result<Wakeup> {
+    loop {
+        if PENDING_SIGNAL.load(Ordering::SeqCst) != 0 {
+            return Ok(Wakeup::Signal);
+        }
+
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(Wakeup::Deadline);
+        }
+
+        if event::poll(SIGNAL_POLL_INTERVAL)? {
+            return Ok(Wakeup::Event(event::read()?));
+        }
+
+        if watcher.as_mut().is_some_and(|watcher| watcher.poll()) {
+            return Ok(Wakeup::FilesChanged);
+        }
+    }
+}
+
+fn setup_terminal<B>(terminal: &Rc<RefCell<Terminal<B>>>) -> Result<()>
+where
+    B: Backend + std::io::Write,
+{
+    let mut terminal = terminal.borrow_mut();
+
+    // setup terminal
+    IS_TERMINAL_SETUP.store(true, Ordering::SeqCst);
+    logger::suspend();
+    enable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        Hide,
+        EnterAlternateScreen,
+        EnableMouseCapture
+    )?;
+
+    Ok(())
+}
+
+fn restore_terminal<B>(terminal: &Rc<RefCell<Terminal<B>>>) -> Result<()>
+where
+    B: Backend + std::io::Write,
+{
+    let mut terminal = terminal.borrow_mut();
+    terminal.clear()?;
+
+    // restore terminal
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        Show,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+    )?;
+    IS_TERMINAL_SETUP.store(false, Ordering::SeqCst);
+    logger::resume();
 
     Ok(())
 }
 
 fn ui(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    if area.width < MIN_TERMINAL_WIDTH || area.height < MIN_TERMINAL_HEIGHT {
+        render_too_small(f, app, area);
+        return;
+    }
+
     let rects = Layout::vertical([
         Constraint::Length(3),
         Constraint::Min(5),
         Constraint::Length(3),
     ])
-    .split(f.area());
+    .split(area);
 
     render_searchbar(f, app, rects[0]);
 
-    render_table(f, app, rects[1]);
+    if app.config.show_proxy_chain {
+        let table_rects =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(rects[1]);
+        render_table(f, app, table_rects[0]);
+        render_proxy_chain(f, app, table_rects[1]);
+    } else {
+        render_table(f, app, rects[1]);
+    }
 
     render_footer(f, app, rects[2]);
 
+    if app.dry_run_output.is_some() {
+        render_dry_run(f, app, rects[1]);
+    }
+
+    // Kept inside the search bar, before the right border
     let mut cursor_position = rects[0].as_position();
-    cursor_position.x += u16::try_from(app.search.cursor()).unwrap_or_default() + 4;
+    cursor_position.x = min(
+        cursor_position
+            .x
+            .saturating_add(u16::try_from(app.search.cursor()).unwrap_or(u16::MAX))
+            .saturating_add(4),
+        rects[0].right().saturating_sub(2),
+    );
     cursor_position.y += 1;
 
     f.set_cursor_position(cursor_position);
 }
 
+fn render_too_small(f: &mut Frame, app: &App, area: Rect) {
+    let lines = vec![
+        Line::from(app.messages.terminal_too_small),
+        Line::from(format!("{}x{}", area.width, area.height)),
+        Line::from(messages::format(
+            app.messages.terminal_needs,
+            &[&MIN_TERMINAL_WIDTH, &MIN_TERMINAL_HEIGHT],
+        )),
+    ];
+
+    let height = min(u16::try_from(lines.len()).unwrap_or(u16::MAX), area.height);
+    let message_area = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+
+    f.render_widget(
+        Paragraph::new(lines).centered().wrap(Wrap { trim: true }),
+        message_area,
+    );
+}
+
 fn render_searchbar(f: &mut Frame, app: &mut App, area: Rect) {
     let info_footer = Paragraph::new(Line::from(app.search.value())).block(
         Block::default()
@@ -447,62 +1896,186 @@ fn render_searchbar(f: &mut Frame, app: &mut App, area: Rect) {
 
 fn render_table(f: &mut Frame, app: &mut App, area: Rect) {
     let header_style = Style::default().fg(tailwind::CYAN.c500);
-    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+    let invalid_style = Style::default().fg(tailwind::RED.c500);
 
-    let mut header_names = vec!["Name", "Aliases", "User", "Destination", "Port"];
-    if app.config.show_proxy_command {
-        header_names.push("Proxy");
-    }
+    let columns = app.table_columns();
 
-    let header = header_names
+    let header = columns
         .iter()
-        .copied()
-        .map(Cell::from)
+        .map(|column| Cell::from(column.title()))
         .collect::<Row>()
         .style(header_style)
         .height(1);
 
-    let rows = app.hosts.iter().map(|host| {
-        let mut content = vec![
-            host.name.clone(),
-            host.aliases.clone(),
-            host.user.clone().unwrap_or_default(),
-            host.destination.clone(),
-            host.port.clone().unwrap_or_default(),
-        ];
-        if app.config.show_proxy_command {
-            content.push(host.proxy_command.clone().unwrap_or_default());
-        }
+    // Long columns can't take more than a third of the table, -1 for the padding
+    let max_column_width = max(area.width / 3, MIN_MAX_COLUMN_WIDTH);
+    // Unless the column has a larger minimum width, +1 for the padding
+    let max_width = |column| {
+        max(
+            max_column_width,
+            app.config.min_column_widths.get(column).saturating_add(1),
+        )
+    };
+    let constraints = app
+        .table_columns_constraints
+        .iter()
+        .zip(&columns)
+        .map(|(constraint, &column)| {
+            let max_width = max_width(column);
+            match *constraint {
+                Constraint::Length(width) => Constraint::Length(min(width, max_width)),
+                Constraint::Min(width) => Constraint::Min(min(width, max_width)),
+                constraint => constraint,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let selected = app.table_state.selected();
+    let matcher = SkimMatcherV2::default();
+    let rows = app.hosts.iter().enumerate().map(|(index, host)| {
+        let row_style = match host
+            .label_color
+            .as_deref()
+            .and_then(|color| color.parse::<Color>().ok())
+        {
+            Some(color) => Style::default().fg(color),
+            None => Style::default(),
+        };
+
+        let expand_aliases = app.expand_aliases || selected == Some(index);
+        let mut height = 1;
 
-        content
+        let row = columns
             .iter()
-            .map(|content| Cell::from(Text::from(content.to_string())))
-            .collect::<Row>()
+            .map(|&column| {
+                let max_cell_width = usize::from(max_width(column) - 1);
+
+                if column == ssh::HostField::Aliases {
+                    let lines = if expand_aliases {
+                        wrap_aliases(&host.aliases, max_cell_width)
+                    } else {
+                        vec![collapse_aliases(&host.aliases, max_cell_width)]
+                    };
+                    height = max(height, lines.len());
+
+                    return Cell::from(
+                        lines
+                            .iter()
+                            .map(|line| truncate(line, max_cell_width))
+                            .collect::<Text>(),
+                    );
+                }
+
+                let style = if column == ssh::HostField::Port && host.has_invalid_port {
+                    invalid_style
+                } else {
+                    Style::default()
+                };
+
+                let value = if column == ssh::HostField::Name {
+                    name_cell_value(app, host, &matcher)
+                } else {
+                    column_value(host, column, &app.config)
+                };
+
+                let value = truncate(&value, max_cell_width);
+                Cell::from(Text::from(value)).style(style)
+            })
+            .collect::<Row>();
+
+        row.height(u16::try_from(height).unwrap_or(u16::MAX))
+            .style(row_style)
     });
 
-    let bar = " █ ";
-    let t = Table::new(rows, app.table_columns_constraints.clone())
+    let highlight_symbol = if app.hosts.is_empty() {
+        Text::default()
+    } else {
+        app.config.highlight.symbol.clone()
+    };
+
+    let t = Table::new(rows, constraints)
         .header(header)
-        .row_highlight_style(selected_style)
-        .highlight_symbol(Text::from(vec![
-            "".into(),
-            bar.into(),
-            bar.into(),
-            "".into(),
-        ]))
+        .row_highlight_style(app.config.highlight.style)
+        .highlight_symbol(highlight_symbol)
         .highlight_spacing(HighlightSpacing::Always)
+        .block(table_block(app, area));
+
+    app.table_area = area;
+    f.render_stateful_widget(t, area, &mut app.table_state);
+
+    if app.hosts.is_empty() {
+        render_empty_state(f, app, area);
+    }
+}
+
+fn table_block(app: &App, area: Rect) -> Block<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::new().fg(app.palette.c400))
+        .border_type(BorderType::Rounded);
+
+    // -2 for the borders, -1 for the header
+    let visible_rows = usize::from(area.height.saturating_sub(3));
+    if app.hosts.len() <= visible_rows {
+        return block;
+    }
+
+    let selected = min(app.table_state.selected().unwrap_or(0) + 1, app.hosts.len());
+    block.title_bottom(Line::from(format!(" {selected}/{} ", app.hosts.len())).right_aligned())
+}
+
+fn render_empty_state(f: &mut Frame, app: &App, area: Rect) {
+    let search = app.search.value().trim();
+    let message = if search.is_empty() {
+        app.messages.no_hosts.to_string()
+    } else {
+        messages::format(app.messages.no_matches, &[&search])
+    };
+
+    // Inside the borders, below the header
+    let area = area.inner(Margin::new(1, 1));
+    let area = Rect {
+        y: area.y + 1,
+        height: area.height.saturating_sub(1),
+        ..area
+    };
+    let message_area = Rect {
+        y: area.y + area.height.saturating_sub(1) / 2,
+        height: min(1, area.height),
+        ..area
+    };
+
+    f.render_widget(
+        Paragraph::new(message)
+            .centered()
+            .style(Style::default().fg(app.palette.c400)),
+        message_area,
+    );
+}
+
+fn render_proxy_chain(f: &mut Frame, app: &App, area: Rect) {
+    let lines = app.render_proxy_chain();
+
+    let pane = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+        .wrap(Wrap { trim: false })
         .block(
             Block::default()
+                .title(format!(" {} ", app.messages.proxy_chain))
                 .borders(Borders::ALL)
                 .border_style(Style::new().fg(app.palette.c400))
-                .border_type(BorderType::Rounded),
+                .border_type(BorderType::Rounded)
+                .padding(Padding::horizontal(1)),
         );
 
-    f.render_stateful_widget(t, area, &mut app.table_state);
+    f.render_widget(pane, area);
 }
 
 fn render_footer(f: &mut Frame, app: &mut App, area: Rect) {
-    let info_footer = Paragraph::new(Line::from(INFO_TEXT)).centered().block(
+    // -2 for the borders
+    let width = usize::from(area.width.saturating_sub(2));
+    let info_text = fit_hints(&app.footer_hints(), width);
+
+    let info_footer = Paragraph::new(Line::from(info_text)).centered().block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::new().fg(app.palette.c400))
@@ -510,3 +2083,520 @@ fn render_footer(f: &mut Frame, app: &mut App, area: Rect) {
     );
     f.render_widget(info_footer, area);
 }
+
+fn render_dry_run(f: &mut Frame, app: &App, area: Rect) {
+    let lines = app.dry_run_output.clone().unwrap_or_default();
+
+    // +2 for the borders
+    let height = u16::try_from(lines.len())
+        .unwrap_or(u16::MAX)
+        .saturating_add(2);
+    let popup_area = area.inner(Margin::new(2, 0));
+    let popup_area = Rect {
+        y: popup_area.y + popup_area.height.saturating_sub(height) / 2,
+        height: min(height, popup_area.height),
+        ..popup_area
+    };
+
+    let popup = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .title(format!(" {} ", app.messages.dry_run))
+                .borders(Borders::ALL)
+                .border_style(Style::new().fg(app.palette.c400))
+                .border_type(BorderType::Rounded)
+                .padding(Padding::horizontal(1)),
+        );
+
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// App of the hosts of the SSH configuration, with the `config` settings.
+    fn test_app(ssh_config: &str, config: &AppConfig) -> App {
+        App::from_reader(config, &mut ssh_config.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_navigate_empty_search() {
+        let mut app = test_app(
+            "Host web\n\nHost db\n",
+            &AppConfig {
+                search_fields: vec![ssh::HostField::Name],
+                search_filter: Some("nonexistent".to_string()),
+                ..AppConfig::default()
+            },
+        );
+
+        assert!(app.hosts.is_empty());
+
+        for action in [
+            Action::Last,
+            Action::Up,
+            Action::Down,
+            Action::First,
+            Action::PageDown,
+            Action::PageUp,
+            Action::HalfPageDown,
+        ] {
+            app.navigate(action);
+            assert_eq!(app.table_state.selected(), Some(0), "{action}");
+        }
+
+        app.set_search(String::new());
+        app.navigate(Action::Last);
+        assert_eq!(app.table_state.selected(), Some(1));
+        app.navigate(Action::Down);
+        assert_eq!(app.table_state.selected(), Some(0));
+        app.navigate(Action::Up);
+        assert_eq!(app.table_state.selected(), Some(1));
+    }
+
+    #[test]
+    fn test_navigate_no_wrap() {
+        let mut app = test_app(
+            "Host web\n\nHost db\n",
+            &AppConfig {
+                no_wrap: true,
+                ..AppConfig::default()
+            },
+        );
+
+        for (action, selected) in [
+            (Action::Up, 0),
+            (Action::Down, 1),
+            (Action::Down, 1),
+            (Action::Up, 0),
+        ] {
+            app.navigate(action);
+            assert_eq!(app.table_state.selected(), Some(selected), "{action}");
+        }
+    }
+
+    #[test]
+    fn test_unique_host() {
+        let ssh_config = "Host web\n\nHost webapp www\n  Hostname app.example.com\n\nHost db\n";
+        let config = AppConfig {
+            search_fields: vec![ssh::HostField::Name, ssh::HostField::Aliases],
+            ..AppConfig::default()
+        };
+
+        let mut app = test_app(ssh_config, &config);
+        app.set_search("db".to_string());
+        assert_eq!(app.hosts.len(), 1);
+        assert!(app.unique_host().is_none());
+
+        let mut app = test_app(
+            ssh_config,
+            &AppConfig {
+                enter_on_unique: true,
+                ..config
+            },
+        );
+        let unique_host_name = |app: &App| app.unique_host().map(|host| host.name.clone());
+
+        app.set_search("db".to_string());
+        assert_eq!(unique_host_name(&app).as_deref(), Some("db"));
+
+        app.set_search("www".to_string());
+        assert_eq!(unique_host_name(&app).as_deref(), Some("webapp"));
+
+        app.set_search("web".to_string());
+        assert_eq!(app.hosts.len(), 2);
+        assert_eq!(unique_host_name(&app).as_deref(), Some("web"));
+
+        app.set_search("we".to_string());
+        assert_eq!(unique_host_name(&app), None);
+
+        app.set_search(String::new());
+        assert_eq!(unique_host_name(&app), None);
+    }
+
+    #[test]
+    fn test_connect_first() {
+        let ssh_config = "Host prod-web\nHost prod-db\n";
+        let config = AppConfig {
+            search_fields: vec![ssh::HostField::Name],
+            dry_run: true,
+            ..AppConfig::default()
+        };
+        let mut app = test_app(
+            ssh_config,
+            &AppConfig {
+                search_filter: Some("prod-web".to_string()),
+                ..config.clone()
+            },
+        );
+        assert_eq!(app.hosts.iter().next().unwrap().name, "prod-web");
+        assert_eq!(app.connect_first().unwrap(), 0);
+
+        let mut no_match = test_app(
+            ssh_config,
+            &AppConfig {
+                search_filter: Some("staging".to_string()),
+                ..config
+            },
+        );
+        let err = no_match.connect_first().unwrap_err();
+        assert_eq!(err.to_string(), "No host matches `staging`");
+    }
+
+    #[test]
+    fn test_toggle_favorite() {
+        let mut app = test_app(
+            "Host a\n\nHost b\n\nHost c\n",
+            &AppConfig {
+                search_fields: vec![ssh::HostField::Name],
+                ..AppConfig::default()
+            },
+        );
+
+        let names = |app: &App| {
+            app.hosts
+                .iter()
+                .map(|host| host.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        app.navigate(Action::Last);
+        app.toggle_favorite();
+        assert_eq!(names(&app), ["c", "a", "b"]);
+        assert_eq!(app.table_state.selected(), Some(0));
+
+        app.set_search("b".to_string());
+        assert_eq!(names(&app), ["b"]);
+
+        app.set_search(String::new());
+        app.navigate(Action::First);
+        app.toggle_favorite();
+        assert_eq!(names(&app), ["a", "b", "c"]);
+        assert_eq!(app.table_state.selected(), Some(2));
+    }
+
+    #[test]
+    fn test_toggle_selection() {
+        let mut app = test_app(
+            "Host a\n\nHost b\n\nHost c\n",
+            &AppConfig {
+                search_fields: vec![ssh::HostField::Name],
+                ..AppConfig::default()
+            },
+        );
+
+        let selection_names = |app: &App| {
+            app.selection_hosts()
+                .into_iter()
+                .map(|host| host.name)
+                .collect::<Vec<_>>()
+        };
+
+        app.navigate(Action::Last);
+        app.toggle_selection();
+        app.navigate(Action::First);
+        app.toggle_selection();
+        assert_eq!(selection_names(&app), ["a", "c"]);
+
+        // Hosts hidden by the search stay selected
+        app.set_search("b".to_string());
+        app.toggle_selection();
+        assert_eq!(selection_names(&app), ["a", "b", "c"]);
+
+        app.toggle_selection();
+        assert_eq!(selection_names(&app), ["a", "c"]);
+    }
+
+    #[test]
+    fn test_reverse_sort() {
+        let mut app = test_app(
+            "Host b\n\nHost a\n\nHost c\n",
+            &AppConfig {
+                sort_dir: ssh::SortDir::Desc,
+                ..AppConfig::default()
+            },
+        );
+
+        let names = |app: &App| {
+            app.hosts
+                .iter()
+                .map(|host| host.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&app), ["c", "b", "a"]);
+
+        app.navigate(Action::Down);
+        app.reverse_sort();
+        assert_eq!(names(&app), ["a", "b", "c"]);
+        assert_eq!(app.table_state.selected(), Some(1));
+
+        app.navigate(Action::Last);
+        app.reverse_sort();
+        assert_eq!(names(&app), ["c", "b", "a"]);
+        assert_eq!(app.table_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_table_columns() {
+        // The source column needs a file to show the path of
+        let path = std::env::temp_dir().join("sshs-test-table-columns");
+        std::fs::write(&path, "Host web\n  Port 2222\n").unwrap();
+
+        let app = App::new(&AppConfig {
+            config_paths: vec![path.display().to_string()],
+            columns: vec![ssh::HostField::Port, ssh::HostField::Name],
+            show_source: true,
+            ..AppConfig::default()
+        });
+        std::fs::remove_file(&path).unwrap();
+
+        let mut app = app.unwrap();
+
+        assert_eq!(
+            app.table_columns(),
+            [
+                ssh::HostField::Port,
+                ssh::HostField::Name,
+                ssh::HostField::Source
+            ]
+        );
+        assert_eq!(app.table_columns_constraints.len(), 3);
+
+        app.config.columns.clear();
+        assert_eq!(app.table_columns()[0], ssh::HostField::Name);
+    }
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join("sshs-test-reload");
+        std::fs::write(&path, "Host db\nHost web\n").unwrap();
+
+        let app = App::new(&AppConfig {
+            config_paths: vec![path.display().to_string()],
+            watch: true,
+            ..AppConfig::default()
+        });
+        let mut app = app.unwrap();
+        app.next();
+        app.toggle_selection();
+
+        std::fs::write(&path, "Host cache\nHost web\nHost db\n").unwrap();
+        app.reload();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(app.hosts.len(), 3);
+        assert_eq!(app.selected_host().unwrap().name, "web");
+        assert!(app.selection.contains("web"));
+        assert_eq!(app.status_message.as_deref(), Some(app.messages.reloaded));
+        assert!(app.status_message_deadline.is_some());
+        assert!(matches!(
+            read_event(None, Some(Instant::now())).unwrap(),
+            Wakeup::Deadline
+        ));
+
+        app.reload();
+        assert_eq!(app.hosts.len(), 3);
+        assert!(app.status_message.unwrap().starts_with("failed to reload"));
+    }
+
+    #[test]
+    fn test_edit_search() {
+        let mut app = test_app(
+            "Host web\nHost db\n",
+            &AppConfig {
+                search_fields: vec![ssh::HostField::Name],
+                ..AppConfig::default()
+            },
+        );
+
+        app.set_search("db".to_string());
+        assert_eq!(app.hosts.len(), 1);
+
+        app.edit_search(InputRequest::GoToStart);
+        assert_eq!(app.search.cursor(), 0);
+        app.edit_search(InputRequest::GoToEnd);
+        assert_eq!(app.search.cursor(), 2);
+
+        let ctrl_e = KeyEvent::new(KeyCode::Char('e'), event::KeyModifiers::CONTROL);
+        assert_eq!(app.key_action(&ctrl_e), Some(Action::SearchEnd));
+
+        app.edit_search(InputRequest::DeletePrevWord);
+        assert_eq!(app.search.value(), "");
+        assert_eq!(app.hosts.len(), 2);
+        assert_eq!(app.key_action(&ctrl_e), Some(Action::ToggleProxyChain));
+    }
+
+    #[test]
+    fn test_history_keys() {
+        let mut search_history = History::default();
+        search_history.push("db").unwrap();
+        let mut app = test_app(
+            "Host web
+Host db
+",
+            &AppConfig {
+                search_fields: vec![ssh::HostField::Name],
+                search_history,
+                ..AppConfig::default()
+            },
+        );
+
+        let ctrl_p = KeyEvent::new(KeyCode::Char('p'), event::KeyModifiers::CONTROL);
+        let ctrl_n = KeyEvent::new(KeyCode::Char('n'), event::KeyModifiers::CONTROL);
+        assert_eq!(app.key_action(&ctrl_p), Some(Action::Up));
+        assert_eq!(app.key_action(&ctrl_n), Some(Action::Down));
+
+        app.set_search("nonexistent".to_string());
+        app.navigate(Action::Up);
+        assert_eq!(app.search.value(), "db");
+        assert_eq!(app.key_action(&ctrl_p), Some(Action::HistoryPrevious));
+        assert_eq!(app.key_action(&ctrl_n), Some(Action::HistoryNext));
+
+        app.history_next();
+        assert_eq!(app.search.value(), "nonexistent");
+        assert_eq!(app.key_action(&ctrl_n), Some(Action::Down));
+    }
+
+    #[test]
+    fn test_labels() {
+        let labels = Labels::from_document(
+            &crate::config_file::Document::parse(
+                "[\"prod-*\"]\ncolor = \"red\"\nlabel = \"PROD\"\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let app = test_app(
+            "Host prod-web\nHost dev\n",
+            &AppConfig {
+                labels,
+                ..AppConfig::default()
+            },
+        );
+
+        let labels = app
+            .hosts
+            .iter()
+            .map(|host| {
+                (
+                    host.name.as_str(),
+                    host.label.as_deref(),
+                    host.label_color.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [("dev", None, None), ("prod-web", Some("PROD"), Some("Red"))]
+        );
+    }
+
+    #[test]
+    fn test_hide_empty_columns() {
+        let config = AppConfig {
+            search_fields: vec![ssh::HostField::Name],
+            min_column_widths: MinColumnWidths::from_document(
+                &crate::config_file::Document::parse("[min_column_widths]\nuser = 20\n").unwrap(),
+            )
+            .unwrap(),
+            ..AppConfig::default()
+        };
+        let mut app = test_app("Host web\n  User root\nHost db\n", &config);
+
+        assert_eq!(
+            app.table_columns(),
+            [
+                ssh::HostField::Name,
+                ssh::HostField::User,
+                ssh::HostField::Destination
+            ]
+        );
+        assert_eq!(app.table_columns_constraints[1], Constraint::Min(21));
+
+        app.set_search("db".to_string());
+        assert_eq!(
+            app.table_columns(),
+            [ssh::HostField::Name, ssh::HostField::Destination]
+        );
+        assert_eq!(app.table_columns_constraints.len(), 2);
+
+        app.set_search("unknown".to_string());
+        assert_eq!(app.table_columns().len(), 5);
+    }
+
+    #[test]
+    fn test_column_value() {
+        let mut host = ssh::Host::ad_hoc("web.example.com").unwrap();
+        let mut config = AppConfig::default();
+
+        let destination = |host: &ssh::Host, config: &AppConfig| {
+            column_value(host, ssh::HostField::Destination, config)
+        };
+        assert_eq!(destination(&host, &config), "web.example.com");
+
+        config.destination_with_user = true;
+        assert_eq!(destination(&host, &config), "web.example.com");
+
+        host.user = Some("admin".to_string());
+        assert_eq!(destination(&host, &config), "admin@web.example.com");
+        assert_eq!(column_value(&host, ssh::HostField::User, &config), "admin");
+
+        let port =
+            |host: &ssh::Host, config: &AppConfig| column_value(host, ssh::HostField::Port, config);
+        assert_eq!(port(&host, &config), "");
+
+        config.default_port = Some(22);
+        assert_eq!(port(&host, &config), "22");
+
+        host.port = Some("2222".to_string());
+        assert_eq!(port(&host, &config), "2222");
+    }
+
+    #[test]
+    fn test_collapse_aliases() {
+        assert_eq!(collapse_aliases("", 10), "");
+        assert_eq!(collapse_aliases("a, b, c", 10), "a, b, c");
+        assert_eq!(collapse_aliases("alpha, beta, gamma", 12), "alpha (+2)");
+        assert_eq!(collapse_aliases("alpha, b, c, d", 12), "alpha (+3)");
+        assert_eq!(collapse_aliases("alphabet, beta", 8), "(+2)");
+    }
+
+    #[test]
+    fn test_wrap_aliases() {
+        assert_eq!(wrap_aliases("a, b, c", 10), ["a, b, c"]);
+        assert_eq!(
+            wrap_aliases("alpha, beta, gamma, d", 12),
+            ["alpha, beta,", "gamma, d"]
+        );
+        assert_eq!(wrap_aliases("alphabetical, b", 4), ["alphabetical,", "b"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_read_event_pending_signal() {
+        use signal_hook::consts::SIGTERM;
+
+        // Without the terminal set up, the signal would exit the tests
+        IS_TERMINAL_SETUP.store(true, Ordering::SeqCst);
+        handle_termination_signals().unwrap();
+        signal_hook::low_level::raise(SIGTERM).unwrap();
+
+        // The signal is stored by the thread handling the signals
+        for _ in 0..100 {
+            if PENDING_SIGNAL.load(Ordering::SeqCst) != 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let signal = PENDING_SIGNAL.load(Ordering::SeqCst);
+        let event = read_event(None, None);
+        PENDING_SIGNAL.store(0, Ordering::SeqCst);
+        IS_TERMINAL_SETUP.store(false, Ordering::SeqCst);
+
+        assert_eq!(signal, SIGTERM);
+        assert!(matches!(event.unwrap(), Wakeup::Signal));
+    }
+}
@@ -0,0 +1,104 @@
+//! Changes to the SSH configuration files, detected by polling their modification times for
+//! `--watch`.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time without any other change after which a change is reported, editors often write a
+/// file several times when saving it.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    /// Watched paths with their last seen modification time, `None` if they don't exist.
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    /// Time of the last change that isn't reported yet.
+    changed_at: Option<Instant>,
+}
+
+impl FileWatcher {
+    #[must_use]
+    pub fn new(paths: Vec<PathBuf>) -> FileWatcher {
+        let mut watcher = FileWatcher::default();
+        watcher.watch(paths);
+        watcher
+    }
+
+    /// Replaces the watched paths, forgetting the changes that aren't reported yet.
+    pub fn watch(&mut self, mut paths: Vec<PathBuf>) {
+        paths.sort();
+        paths.dedup();
+
+        self.files = paths
+            .into_iter()
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect();
+        self.changed_at = None;
+    }
+
+    /// Returns `true` once a watched path was created, modified or removed, and nothing else
+    /// changed during the [`DEBOUNCE`] time since.
+    pub fn poll(&mut self) -> bool {
+        self.poll_at(Instant::now())
+    }
+
+    fn poll_at(&mut self, now: Instant) -> bool {
+        for (path, last_modified) in &mut self.files {
+            let modified = modified(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                self.changed_at = Some(now);
+            }
+        }
+
+        match self.changed_at {
+            Some(changed_at) if now.duration_since(changed_at) >= DEBOUNCE => {
+                self.changed_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll() {
+        let directory = std::env::temp_dir().join("sshs-test-watcher");
+        let path = directory.join("config");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let mut watcher = FileWatcher::new(vec![path.clone()]);
+        let start = Instant::now();
+        let unchanged = watcher.poll_at(start);
+
+        std::fs::write(&path, "Host web\n").unwrap();
+        let changing = watcher.poll_at(start);
+        let debounced = watcher.poll_at(start + DEBOUNCE);
+        let reported_once = watcher.poll_at(start + DEBOUNCE * 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        let removed = watcher.poll_at(start + DEBOUNCE * 3);
+        let removed_debounced = watcher.poll_at(start + DEBOUNCE * 4);
+
+        assert!(!unchanged);
+        assert!(!changing);
+        assert!(debounced);
+        assert!(!reported_once);
+        assert!(!removed);
+        assert!(removed_debounced);
+    }
+}